@@ -0,0 +1,180 @@
+// ### Sprite Atlas
+
+pub const ATLAS_WIDTH: u32 = 128;
+pub const ATLAS_HEIGHT: u32 = 64;
+const CELL_SIZE: u32 = 32; // Every sprite has a 32x32 cell
+
+/// Images in the atlas. Sprites are greyscale or white so they can be tinted by vertex colour.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sprite {
+    /// Plain white, sampled by untextured shapes so they keep their flat colour.
+    White,
+    Brick,
+    CracksLight,
+    CracksMedium,
+    CracksHeavy,
+    Bullet,
+    Barrel,
+    Base,
+}
+
+/// Texture coordinates of a sprite, with v pointing down the image.
+#[derive(Clone, Copy)]
+pub struct Region {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Sprite {
+    /// Crack overlay for a brick with `fraction` of its health left, if it is damaged enough.
+    pub fn cracks_for_health(fraction: f32) -> Option<Sprite> {
+        if fraction > 0.75 {
+            None
+        } else if fraction > 0.5 {
+            Some(Sprite::CracksLight)
+        } else if fraction > 0.25 {
+            Some(Sprite::CracksMedium)
+        } else {
+            Some(Sprite::CracksHeavy)
+        }
+    }
+
+    /// Column and row of the sprite's cell.
+    fn cell(self) -> (u32, u32) {
+        match self {
+            Sprite::White => (0, 0),
+            Sprite::Brick => (1, 0),
+            Sprite::CracksLight => (2, 0),
+            Sprite::CracksMedium => (3, 0),
+            Sprite::CracksHeavy => (0, 1),
+            Sprite::Bullet => (1, 1),
+            Sprite::Barrel => (2, 1),
+            Sprite::Base => (3, 1),
+        }
+    }
+
+    pub fn region(self) -> Region {
+        let (column, row) = self.cell();
+        // Inset by half a texel so filtering never samples the neighbouring cell
+        let left = (column * CELL_SIZE) as f32 + 0.5;
+        let top = (row * CELL_SIZE) as f32 + 0.5;
+        let size = CELL_SIZE as f32 - 1.0;
+        Region {
+            left: left / ATLAS_WIDTH as f32,
+            top: top / ATLAS_HEIGHT as f32,
+            right: (left + size) / ATLAS_WIDTH as f32,
+            bottom: (top + size) / ATLAS_HEIGHT as f32,
+        }
+    }
+}
+
+/// Texture coordinate inside the white cell, for flat-coloured shapes.
+pub const WHITE_UV: [f32; 2] = [
+    (CELL_SIZE / 2) as f32 / ATLAS_WIDTH as f32,
+    (CELL_SIZE / 2) as f32 / ATLAS_HEIGHT as f32,
+];
+
+// ### Built-in Atlas
+
+/// Draws the built-in atlas as RGBA bytes, rows from the top.
+///
+/// This is used until an atlas image with the same layout has loaded, or if none is configured.
+pub fn generate() -> Vec<u8> {
+    let mut pixels = vec![0; (ATLAS_WIDTH * ATLAS_HEIGHT * 4) as usize];
+    let mut put = |sprite: Sprite, x: u32, y: u32, rgba: [u8; 4]| {
+        let (column, row) = sprite.cell();
+        let index = (((row * CELL_SIZE + y) * ATLAS_WIDTH + column * CELL_SIZE + x) * 4) as usize;
+        pixels[index..index + 4].copy_from_slice(&rgba);
+    };
+    let grey = |value: f32, alpha: f32| {
+        let value = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        [
+            value,
+            value,
+            value,
+            (alpha.clamp(0.0, 1.0) * 255.0).round() as u8,
+        ]
+    };
+
+    let mut random = Lcg(0x2545_f491);
+    for y in 0..CELL_SIZE {
+        for x in 0..CELL_SIZE {
+            put(Sprite::White, x, y, [255; 4]);
+
+            // Bevelled brick: lit from the top-left with a little grain
+            let brick = if x < 3 || y < 3 {
+                1.0
+            } else if x >= CELL_SIZE - 3 || y >= CELL_SIZE - 3 {
+                0.55
+            } else {
+                0.85 + (random.next() - 0.5) * 0.08
+            };
+            put(Sprite::Brick, x, y, grey(brick, 1.0));
+
+            // Soft round bullet
+            let (dx, dy) = (x as f32 + 0.5 - 16.0, y as f32 + 0.5 - 16.0);
+            let distance = (dx * dx + dy * dy).sqrt() / 16.0;
+            put(
+                Sprite::Bullet,
+                x,
+                y,
+                grey(1.0, (1.0 - distance).max(0.0).sqrt()),
+            );
+
+            // Cylindrical barrel with two bands near the base
+            let shade = 0.6 + 0.4 * (std::f32::consts::PI * (x as f32 + 0.5) / 32.0).sin();
+            let band = if y == 26 || y == 27 { 0.7 } else { 1.0 };
+            put(Sprite::Barrel, x, y, grey(shade * band, 1.0));
+
+            // Base plate with a dark rim and four rivets
+            let near =
+                |cx: f32, cy: f32| (x as f32 - cx).abs() < 2.0 && (y as f32 - cy).abs() < 2.0;
+            let base = if x < 2 || y < 2 || x >= CELL_SIZE - 2 || y >= CELL_SIZE - 2 {
+                0.6
+            } else if near(6.0, 6.0) || near(25.0, 6.0) || near(6.0, 25.0) || near(25.0, 25.0) {
+                1.0
+            } else {
+                0.85
+            };
+            put(Sprite::Base, x, y, grey(base, 1.0));
+        }
+    }
+
+    // Cracks: more jagged lines the more damaged the brick
+    let levels = [
+        (Sprite::CracksLight, 1),
+        (Sprite::CracksMedium, 2),
+        (Sprite::CracksHeavy, 4),
+    ];
+    for &(sprite, cracks) in levels.iter() {
+        for _ in 0..cracks {
+            let mut x = 8.0 + random.next() * 16.0;
+            let mut y = 8.0 + random.next() * 16.0;
+            let mut angle = random.next() * 2.0 * std::f32::consts::PI;
+            for _ in 0..24 {
+                angle += (random.next() - 0.5) * 1.2;
+                x += angle.cos();
+                y += angle.sin();
+                if x < 0.0 || y < 0.0 || x >= CELL_SIZE as f32 || y >= CELL_SIZE as f32 {
+                    break;
+                }
+                put(sprite, x as u32, y as u32, [20, 20, 20, 220]);
+            }
+        }
+    }
+
+    pixels
+}
+
+/// Small deterministic generator so the built-in atlas is identical on every run.
+struct Lcg(u32);
+
+impl Lcg {
+    /// Next value in 0.0..1.0.
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (self.0 >> 8) as f32 / (1 << 24) as f32
+    }
+}
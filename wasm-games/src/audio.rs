@@ -0,0 +1,129 @@
+use web_sys::{AudioContext, OscillatorType};
+
+// ### Audio
+
+const AUDIO_MIN_INTERVAL: f64 = 0.03; // Seconds before the same sound can start again
+
+/// Short synthesized effects shared by every game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sound {
+    Bounce, // Ball off a paddle
+    BrickBroken,
+    Pickup,
+    LifeLost,
+    StageCleared,
+    GameOver,
+}
+
+/// Oscillator wave, start and end frequency in Hz, duration in seconds and volume.
+struct Tone {
+    wave: OscillatorType,
+    from: f32,
+    to: f32,
+    duration: f32,
+    volume: f32,
+}
+
+impl Sound {
+    const COUNT: usize = 6;
+
+    fn tone(self) -> Tone {
+        let (wave, from, to, duration, volume) = match self {
+            Sound::Bounce => (OscillatorType::Square, 440.0, 440.0, 0.05, 0.08),
+            Sound::BrickBroken => (OscillatorType::Triangle, 660.0, 220.0, 0.08, 0.15),
+            Sound::Pickup => (OscillatorType::Sine, 520.0, 1040.0, 0.15, 0.2),
+            Sound::LifeLost => (OscillatorType::Sawtooth, 300.0, 80.0, 0.5, 0.15),
+            Sound::StageCleared => (OscillatorType::Sine, 440.0, 880.0, 0.4, 0.2),
+            Sound::GameOver => (OscillatorType::Triangle, 330.0, 55.0, 1.0, 0.25),
+        };
+        Tone {
+            wave,
+            from,
+            to,
+            duration,
+            volume,
+        }
+    }
+}
+
+/// Plays sounds through Web Audio, or stays silent where it is unavailable.
+///
+/// Browsers keep a new audio context suspended until the page is interacted with, so the
+/// engine calls `unlock` on every key press, click and touch.
+pub struct Audio {
+    context: Option<AudioContext>,
+    last_played: [f64; Sound::COUNT], // Context time each sound last started
+}
+
+impl Audio {
+    pub fn open() -> Self {
+        let context = AudioContext::new().ok();
+        if context.is_none() {
+            log::warn!("Web Audio unavailable, the game will be silent");
+        }
+        Audio {
+            context,
+            last_played: [f64::NEG_INFINITY; Sound::COUNT],
+        }
+    }
+
+    pub fn unlock(&self) {
+        if let Some(context) = &self.context {
+            let _ = context.resume();
+        }
+    }
+
+    /// Does what `unlock` does, for DOM listeners to call while handling a gesture.
+    pub fn unlocker(&self) -> impl Fn() + 'static {
+        let context = self.context.clone();
+        move || {
+            if let Some(context) = &context {
+                let _ = context.resume();
+            }
+        }
+    }
+
+    /// Starts `sound`, unless it started too recently to be heard as a separate sound.
+    pub fn play(&mut self, sound: Sound) {
+        let context = match &self.context {
+            Some(context) => context,
+            None => return,
+        };
+        let now = context.current_time();
+        if now - self.last_played[sound as usize] < AUDIO_MIN_INTERVAL {
+            return;
+        }
+        self.last_played[sound as usize] = now;
+        if let Err(error) = start_tone(context, sound.tone(), now) {
+            log::warn!("Cannot play {:?}: {:?}", sound, error);
+        }
+    }
+}
+
+impl Drop for Audio {
+    fn drop(&mut self) {
+        if let Some(context) = &self.context {
+            let _ = context.close();
+        }
+    }
+}
+
+/// Plays `tone` from `now`, gliding between its frequencies while it fades out.
+fn start_tone(context: &AudioContext, tone: Tone, now: f64) -> Result<(), wasm_bindgen::JsValue> {
+    let end = now + tone.duration as f64;
+    let oscillator = context.create_oscillator()?;
+    oscillator.set_type(tone.wave);
+    oscillator.frequency().set_value_at_time(tone.from, now)?;
+    oscillator
+        .frequency()
+        .exponential_ramp_to_value_at_time(tone.to, end)?;
+    let gain = context.create_gain()?;
+    gain.gain().set_value_at_time(tone.volume, now)?;
+    // Exponential ramps can't reach zero, so fade to nearly silent
+    gain.gain().exponential_ramp_to_value_at_time(0.001, end)?;
+    oscillator.connect_with_audio_node(&gain)?;
+    gain.connect_with_audio_node(&context.destination())?;
+    oscillator.start_with_when(now)?;
+    oscillator.stop_with_when(end)?;
+    Ok(())
+}
@@ -0,0 +1,635 @@
+use std::collections::HashSet;
+use winit::event::{MouseButton, TouchPhase};
+
+use crate::atlas::Sprite;
+use crate::audio::Sound;
+use crate::camera::Camera;
+use crate::config::GameConfig;
+use crate::events::GameEvent;
+use crate::game::{BindingRequest, Engine, Game, GameInput, Setting};
+use crate::input::{Action, InputMap};
+use crate::particles::ParticleSystem;
+use crate::playfield::{
+    build_stage, draw_bricks, draw_message, draw_playfield, hud_bounds, Brick, HashGrid,
+    BALL_RADIUS, BRICK_DEBRIS, FLASH_DECAY, FLASH_PER_BRICK, GROUND_TOP, HIT_SPARKS, HUD_TEXT_SIZE,
+    MAX_PARTICLES, SHAKE_DECAY, SHAKE_PER_BRICK, SHAKE_PER_HIT,
+};
+use crate::random::random;
+use crate::renderer::{PostEffects, Renderer, ScreenFilter};
+use crate::storage::Storage;
+use crate::text::{self, TextStyle};
+use crate::theme::{saved_theme, Theme, THEME_KEY};
+
+// ### Breakout
+
+const PADDLE_WIDTH: f32 = 0.25;
+const PADDLE_HEIGHT: f32 = 0.03;
+const PADDLE_Y: f32 = -0.85; // Centre of the Breakout paddle
+const PADDLE_SPEED: f32 = 1.5; // Keyboard and gamepad paddle speed in units per second
+const PADDLE_MAX_BOUNCE_ANGLE: f32 = 1.05; // Off vertical at the paddle's ends, in radians
+const SERVE_MAX_ANGLE: f32 = 0.3; // Largest random angle off vertical of a serve
+const BREAKOUT_LIVES: u32 = 3;
+const BREAKOUT_MAX_BRICK_HEALTH: u32 = 3; // Bricks take one more hit per stage up to this
+const BREAKOUT_SPEEDUP_PER_STAGE: f32 = 0.1; // Extra ball speed each stage, as a fraction
+const BREAKOUT_BINDINGS_KEY: &str = "breakout-bindings";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Serving, // The ball rests on the paddle until the player serves
+    Playing,
+    GameOver,
+}
+
+/// Classic paddle-and-ball Breakout on the cannon game's brick layouts.
+///
+/// The paddle follows the mouse or a finger, or moves with the drive and aim keys and the left
+/// stick. Fire, a click or a tap serves; the ball leaves the paddle at an angle set by where it
+/// lands on it, and a life is lost whenever it reaches the ground.
+pub struct Breakout {
+    config: GameConfig,
+    theme: &'static Theme,
+    input: InputMap,
+    bricks: Vec<Brick>,
+    hash_grid: HashGrid,
+    particles: ParticleSystem,
+    paddle_x: f32,
+    paddle_target: Option<f32>, // Where the mouse or a finger wants the paddle
+    left_pressed: bool,
+    right_pressed: bool,
+    stick: f32, // Gamepad left stick, -1.0 to 1.0
+    touches: Vec<u64>,
+    ball: (f32, f32),
+    ball_velocity: (f32, f32),
+    phase: Phase,
+    lives: u32,
+    score: u32,
+    stage: u32,
+    message: Option<(String, f32)>,
+    trauma: f32,
+    flash: f32,
+    screen_filter: ScreenFilter,
+    reported_score: Option<u32>,
+    sounds: Vec<Sound>,     // Played after every update
+    events: Vec<GameEvent>, // For the page, handed to the engine after every update
+}
+
+impl Breakout {
+    pub fn new(config: GameConfig, storage: &dyn Storage) -> Self {
+        let (bricks, hash_grid) = build_breakout_stage(1, &config);
+        let mut breakout = Breakout {
+            config,
+            theme: saved_theme(storage),
+            input: InputMap::load(storage, BREAKOUT_BINDINGS_KEY, 1),
+            bricks,
+            hash_grid,
+            particles: ParticleSystem::new(MAX_PARTICLES),
+            paddle_x: 0.0,
+            paddle_target: None,
+            left_pressed: false,
+            right_pressed: false,
+            stick: 0.0,
+            touches: Vec::new(),
+            ball: (0.0, 0.0),
+            ball_velocity: (0.0, 0.0),
+            phase: Phase::Serving,
+            lives: BREAKOUT_LIVES,
+            score: 0,
+            stage: 1,
+            message: None,
+            trauma: 0.0,
+            flash: 0.0,
+            screen_filter: ScreenFilter::None,
+            reported_score: None,
+            sounds: Vec::new(),
+            events: Vec::new(),
+        };
+        breakout.wait_for_serve();
+        breakout
+    }
+
+    /// Puts the ball back on the paddle and asks the player to serve.
+    fn wait_for_serve(&mut self) {
+        self.phase = Phase::Serving;
+        self.ball_velocity = (0.0, 0.0);
+        self.message = Some(("Click or tap to serve".to_string(), f32::INFINITY));
+    }
+
+    fn serve(&mut self) {
+        if self.phase != Phase::Serving {
+            return;
+        }
+        let angle = (random() as f32 * 2.0 - 1.0) * SERVE_MAX_ANGLE;
+        let speed = self.ball_speed();
+        self.ball_velocity = (speed * angle.sin(), speed * angle.cos());
+        self.phase = Phase::Playing;
+        self.message = None;
+    }
+
+    /// Serves the ball, or starts a new game once this one is over.
+    fn serve_or_restart(&mut self, storage: &dyn Storage) {
+        if self.phase == Phase::GameOver {
+            self.new_game(storage);
+        } else {
+            self.serve();
+        }
+    }
+
+    /// The configured ball speed, raised for every stage cleared.
+    fn ball_speed(&self) -> f32 {
+        self.config.ball_speed * (1.0 + BREAKOUT_SPEEDUP_PER_STAGE * (self.stage - 1) as f32)
+    }
+
+    /// Starts over from stage 1, keeping the screen filter.
+    fn new_game(&mut self, storage: &dyn Storage) {
+        let screen_filter = self.screen_filter;
+        *self = Breakout::new(self.config, storage);
+        self.screen_filter = screen_filter;
+    }
+
+    fn handle_action(&mut self, storage: &dyn Storage, action: Action, pressed: bool) {
+        match action {
+            Action::AimLeft | Action::DriveLeft => self.left_pressed = pressed,
+            Action::AimRight | Action::DriveRight => self.right_pressed = pressed,
+            Action::Fire if pressed => self.serve_or_restart(storage),
+            Action::CycleFilter if pressed => self.screen_filter = self.screen_filter.next(),
+            Action::Continue if pressed && self.phase == Phase::GameOver => self.new_game(storage),
+            _ => {}
+        }
+        if self.left_pressed || self.right_pressed {
+            self.paddle_target = None; // Keys take over from the mouse
+        }
+    }
+
+    fn move_paddle(&mut self, delta_time: f32) {
+        let mut throttle = self.stick;
+        if self.left_pressed {
+            throttle -= 1.0;
+        }
+        if self.right_pressed {
+            throttle += 1.0;
+        }
+        let throttle = throttle.clamp(-1.0, 1.0);
+        if throttle != 0.0 {
+            self.paddle_x += throttle * PADDLE_SPEED * delta_time;
+            self.paddle_target = None;
+        } else if let Some(target) = self.paddle_target {
+            self.paddle_x = target;
+        }
+        let limit = 1.0 - PADDLE_WIDTH / 2.0;
+        self.paddle_x = self.paddle_x.clamp(-limit, limit);
+    }
+
+    /// Moves the ball in steps no longer than its radius, so it can't pass through a brick or
+    /// the paddle between frames.
+    fn move_ball(&mut self, delta_time: f32) {
+        // Keep the speed in step with the config, which may change mid-game
+        let (dx, dy) = self.ball_velocity;
+        let length = (dx * dx + dy * dy).sqrt().max(1e-6);
+        let speed = self.ball_speed();
+        self.ball_velocity = (dx / length * speed, dy / length * speed);
+
+        let steps = (speed * delta_time / BALL_RADIUS).ceil().max(1.0) as u32;
+        let step_time = delta_time / steps as f32;
+        for _ in 0..steps {
+            self.ball.0 += self.ball_velocity.0 * step_time;
+            self.ball.1 += self.ball_velocity.1 * step_time;
+            self.bounce_off_walls();
+            self.bounce_off_paddle();
+            self.hit_bricks();
+            if self.ball.1 - BALL_RADIUS < GROUND_TOP {
+                self.lose_life();
+                return;
+            }
+        }
+    }
+
+    fn bounce_off_walls(&mut self) {
+        let (x, y) = &mut self.ball;
+        let (dx, dy) = &mut self.ball_velocity;
+        if *x - BALL_RADIUS < -1.0 {
+            *x = -1.0 + BALL_RADIUS;
+            *dx = dx.abs();
+        }
+        if *x + BALL_RADIUS > 1.0 {
+            *x = 1.0 - BALL_RADIUS;
+            *dx = -dx.abs();
+        }
+        if *y + BALL_RADIUS > 1.0 {
+            *y = 1.0 - BALL_RADIUS;
+            *dy = -dy.abs();
+        }
+    }
+
+    /// Sends the ball back up at an angle set by how far from the paddle's centre it lands.
+    fn bounce_off_paddle(&mut self) {
+        let (x, y) = self.ball;
+        let paddle_top = PADDLE_Y + PADDLE_HEIGHT / 2.0;
+        if self.ball_velocity.1 >= 0.0
+            || y - BALL_RADIUS > paddle_top
+            || y + BALL_RADIUS < PADDLE_Y - PADDLE_HEIGHT / 2.0
+            || (x - self.paddle_x).abs() > PADDLE_WIDTH / 2.0 + BALL_RADIUS
+        {
+            return;
+        }
+        let offset = ((x - self.paddle_x) / (PADDLE_WIDTH / 2.0)).clamp(-1.0, 1.0);
+        let angle = offset * PADDLE_MAX_BOUNCE_ANGLE;
+        let speed = self.ball_speed();
+        self.ball_velocity = (speed * angle.sin(), speed * angle.cos());
+        self.ball.1 = paddle_top + BALL_RADIUS;
+        self.sounds.push(Sound::Bounce);
+    }
+
+    /// Damages the first brick the ball overlaps and bounces it off the side it came through.
+    fn hit_bricks(&mut self) {
+        let (x, y) = self.ball;
+        let (i_x_min, i_y_min) = self
+            .hash_grid
+            .get_cell_index(x - BALL_RADIUS, y - BALL_RADIUS);
+        let (i_x_max, i_y_max) = self
+            .hash_grid
+            .get_cell_index(x + BALL_RADIUS, y + BALL_RADIUS);
+        let mut bricks_to_check = HashSet::new();
+        for i_x in i_x_min..=i_x_max {
+            for i_y in i_y_min..=i_y_max {
+                let bricks_in_cell = self.hash_grid.get_bricks_in_cell(i_x, i_y);
+                for &brick_index in bricks_in_cell {
+                    bricks_to_check.insert(brick_index);
+                }
+            }
+        }
+
+        let hit = bricks_to_check.into_iter().find(|&index| {
+            let brick = &self.bricks[index];
+            brick.health > 0
+                && x + BALL_RADIUS > brick.left
+                && x - BALL_RADIUS < brick.right
+                && y + BALL_RADIUS > brick.bottom
+                && y - BALL_RADIUS < brick.top
+        });
+        let brick = match hit {
+            Some(index) => &mut self.bricks[index],
+            None => return,
+        };
+
+        // The shallower overlap is the side the ball came through
+        let overlap_x = (x + BALL_RADIUS - brick.left).min(brick.right - (x - BALL_RADIUS));
+        let overlap_y = (y + BALL_RADIUS - brick.bottom).min(brick.top - (y - BALL_RADIUS));
+        let (center_x, center_y) = (
+            (brick.left + brick.right) / 2.0,
+            (brick.bottom + brick.top) / 2.0,
+        );
+        let (dx, dy) = &mut self.ball_velocity;
+        if overlap_x < overlap_y {
+            *dx = dx.abs() * (x - center_x).signum();
+        } else {
+            *dy = dy.abs() * (y - center_y).signum();
+        }
+
+        let color = brick.color(self.theme);
+        brick.health -= 1;
+        brick.hit_timer = self.config.hit_effect_duration;
+        self.particles.emit(&HIT_SPARKS.tinted(color), x, y, 0.0);
+        self.trauma = (self.trauma + SHAKE_PER_HIT).min(1.0);
+        if brick.health > 0 {
+            self.sounds.push(Sound::Bounce);
+            return;
+        }
+        self.score += 1;
+        self.trauma = (self.trauma + SHAKE_PER_BRICK).min(1.0);
+        self.flash = (self.flash + FLASH_PER_BRICK).min(1.0);
+        self.particles
+            .emit(&BRICK_DEBRIS.tinted(color), center_x, center_y, 0.0);
+        self.sounds.push(Sound::BrickBroken);
+        self.events.push(GameEvent::BrickDestroyed {
+            player: 0,
+            x: center_x,
+            y: center_y,
+        });
+    }
+
+    fn lose_life(&mut self) {
+        self.lives = self.lives.saturating_sub(1);
+        self.trauma = 1.0;
+        if self.lives > 0 {
+            self.sounds.push(Sound::LifeLost);
+            self.wait_for_serve();
+            return;
+        }
+        self.phase = Phase::GameOver;
+        self.message = Some((
+            "Game over\nClick, tap or press Enter to play again".to_string(),
+            f32::INFINITY,
+        ));
+        self.sounds.push(Sound::GameOver);
+        self.events.push(GameEvent::GameOver {
+            stage: self.stage,
+            scores: vec![self.score],
+        });
+    }
+
+    fn next_stage(&mut self) {
+        self.sounds.push(Sound::StageCleared);
+        self.events
+            .push(GameEvent::StageCleared { stage: self.stage });
+        self.stage += 1;
+        let (bricks, hash_grid) = build_breakout_stage(self.stage, &self.config);
+        self.bricks = bricks;
+        self.hash_grid = hash_grid;
+        self.wait_for_serve();
+        let message = format!("Stage {}\nClick or tap to serve", self.stage);
+        self.message = Some((message, f32::INFINITY));
+    }
+
+    /// Advances the game, leaving its sounds and events for `update` to hand to the engine.
+    fn step(&mut self, delta_time: f32) {
+        if let Some((_, remaining)) = &mut self.message {
+            *remaining -= delta_time;
+            if *remaining <= 0.0 {
+                self.message = None;
+            }
+        }
+        self.trauma = (self.trauma - SHAKE_DECAY * delta_time).max(0.0);
+        self.flash = (self.flash - FLASH_DECAY * delta_time).max(0.0);
+        if self.reported_score != Some(self.score) {
+            self.events.push(GameEvent::ScoreChanged {
+                player: 0,
+                score: self.score,
+            });
+            self.reported_score = Some(self.score);
+        }
+
+        self.particles.set_speed_scale(self.config.particle_speed);
+        self.particles.update(delta_time);
+        for brick in &mut self.bricks {
+            brick.hit_timer = (brick.hit_timer - delta_time).max(0.0);
+        }
+        if self.phase == Phase::GameOver {
+            return;
+        }
+
+        self.move_paddle(delta_time);
+        if self.phase == Phase::Serving {
+            self.ball = (self.paddle_x, PADDLE_Y + PADDLE_HEIGHT / 2.0 + BALL_RADIUS);
+            return;
+        }
+        self.move_ball(delta_time);
+        if self.bricks.iter().all(|brick| brick.health == 0) {
+            self.next_stage();
+        }
+    }
+}
+
+impl Game for Breakout {
+    fn update(&mut self, engine: &mut Engine, delta_time: f32) {
+        self.step(delta_time);
+        for sound in self.sounds.drain(..) {
+            engine.audio.play(sound);
+        }
+        engine.events.append(&mut self.events);
+    }
+
+    fn render(&self, renderer: &mut dyn Renderer, camera: &mut Camera) {
+        let amount = self.config.max_shake * self.trauma * self.trauma;
+        camera.set_shake(
+            (random() as f32 * 2.0 - 1.0) * amount,
+            (random() as f32 * 2.0 - 1.0) * amount,
+        );
+        renderer.set_camera(camera);
+        renderer.set_effects(PostEffects {
+            bloom: self.config.bloom_strength,
+            filter: self.screen_filter,
+            chromatic: self.flash,
+        });
+        renderer.clear(self.theme.clear);
+
+        let theme = self.theme;
+        draw_playfield(renderer, theme, camera);
+        draw_bricks(renderer, &self.bricks, theme);
+        renderer.sprite(
+            Sprite::Base,
+            self.paddle_x - PADDLE_WIDTH / 2.0,
+            PADDLE_Y - PADDLE_HEIGHT / 2.0,
+            self.paddle_x + PADDLE_WIDTH / 2.0,
+            PADDLE_Y + PADDLE_HEIGHT / 2.0,
+            theme.players[0],
+        );
+
+        // Draw the ball and particles glowing
+        renderer.set_glow(1.0);
+        if self.phase != Phase::GameOver {
+            let (x, y) = self.ball;
+            renderer.sprite(
+                Sprite::Bullet,
+                x - BALL_RADIUS,
+                y - BALL_RADIUS,
+                x + BALL_RADIUS,
+                y + BALL_RADIUS,
+                theme.players[0],
+            );
+        }
+        self.particles.draw(renderer);
+        renderer.set_glow(0.0);
+
+        let (left, _, top) = hud_bounds(camera);
+        let hud = format!(
+            "Score {}\nLives {}\nStage {}",
+            self.score, self.lives, self.stage
+        );
+        let style = TextStyle::new(HUD_TEXT_SIZE, theme.text);
+        text::draw_text(renderer, &hud, left + 0.05, top - 0.05, &style);
+        if let Some((message, remaining)) = &self.message {
+            draw_message(renderer, message, *remaining, theme);
+        }
+    }
+
+    fn handle_input(&mut self, engine: &mut Engine, input: GameInput) {
+        match input {
+            GameInput::Key { key, pressed } => {
+                // Bindings name keys after the VirtualKeyCode variants
+                let key = format!("{:?}", key);
+                let actions: Vec<Action> = self
+                    .input
+                    .actions_for(&key)
+                    .map(|(_, action)| action)
+                    .collect();
+                for action in actions {
+                    self.handle_action(engine.storage.as_ref(), action, pressed);
+                }
+            }
+            GameInput::CursorMoved { x, .. } => self.paddle_target = Some(x),
+            GameInput::CursorLeft => {}
+            GameInput::MouseButton { button, pressed } => {
+                if pressed && button == MouseButton::Left {
+                    self.serve_or_restart(engine.storage.as_ref());
+                }
+            }
+            GameInput::Touch { id, phase, x, .. } => {
+                match phase {
+                    TouchPhase::Started => {
+                        // Restarting forgets touches, so serve before tracking this one
+                        self.serve_or_restart(engine.storage.as_ref());
+                        self.touches.push(id);
+                    }
+                    TouchPhase::Moved => {}
+                    TouchPhase::Ended | TouchPhase::Cancelled => {
+                        self.touches.retain(|&touch| touch != id);
+                        return;
+                    }
+                }
+                // The newest finger steers
+                if self.touches.last() == Some(&id) {
+                    self.paddle_target = Some(x);
+                }
+            }
+            GameInput::Gamepads(pads) => {
+                let pad = pads.first();
+                self.stick = pad.map_or(0.0, |pad| pad.drive);
+                if pad.and_then(|pad| pad.fire) == Some(true) {
+                    self.serve_or_restart(engine.storage.as_ref());
+                }
+            }
+            GameInput::GamepadConnection(_) => {}
+        }
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "stage={}\nscore={}\nlives={}\n",
+            self.stage, self.score, self.lives
+        )
+    }
+
+    fn restart(&mut self, engine: &mut Engine) {
+        self.new_game(engine.storage.as_ref());
+    }
+
+    fn configure(&mut self, engine: &mut Engine, setting: Setting) {
+        match setting {
+            Setting::Theme(theme) => {
+                self.theme = theme;
+                engine.storage.save(THEME_KEY, theme.name);
+            }
+            Setting::Config(config) => self.config = config,
+            Setting::Bindings(request) => {
+                match request {
+                    BindingRequest::Bind(player, action, key) => {
+                        if !self.input.bind(player, action, &key) {
+                            log::warn!("Cannot bind {} for player {}", key, player);
+                        }
+                    }
+                    BindingRequest::Clear(player, action) => self.input.clear(player, action),
+                    BindingRequest::Reset => self.input = InputMap::defaults(1),
+                }
+                self.left_pressed = false;
+                self.right_pressed = false;
+                self.input
+                    .save(engine.storage.as_mut(), BREAKOUT_BINDINGS_KEY);
+            }
+            Setting::Touch(_) => {}
+        }
+    }
+}
+
+/// The cannon game's layout for `stage`, with bricks that take one hit more per stage up to
+/// `BREAKOUT_MAX_BRICK_HEALTH`.
+fn build_breakout_stage(stage: u32, config: &GameConfig) -> (Vec<Brick>, HashGrid) {
+    let (mut bricks, hash_grid) = build_stage(stage, config);
+    let health = stage.min(BREAKOUT_MAX_BRICK_HEALTH);
+    for brick in &mut bricks {
+        brick.health = health;
+        brick.max_health = health;
+    }
+    (bricks, hash_grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn breakout() -> Breakout {
+        Breakout::new(GameConfig::default(), &MemoryStorage::default())
+    }
+
+    /// Drops the ball straight onto the paddle, `offset` half-widths from its centre.
+    fn bounce_at(offset: f32) -> (f32, f32) {
+        let mut breakout = breakout();
+        breakout.serve();
+        breakout.ball = (
+            offset * PADDLE_WIDTH / 2.0,
+            PADDLE_Y + PADDLE_HEIGHT / 2.0 + BALL_RADIUS / 2.0,
+        );
+        breakout.ball_velocity = (0.0, -1.0);
+        breakout.bounce_off_paddle();
+        assert_eq!(breakout.sounds, vec![Sound::Bounce]);
+        breakout.ball_velocity
+    }
+
+    #[test]
+    fn paddle_bounce_angle_follows_hit_position() {
+        let speed = breakout().ball_speed();
+        for &offset in &[0.0, 0.5, -0.5, 1.0, -1.0] {
+            let (dx, dy) = bounce_at(offset);
+            let angle = offset * PADDLE_MAX_BOUNCE_ANGLE;
+            assert!((dx - speed * angle.sin()).abs() < 1e-5, "offset {}", offset);
+            assert!((dy - speed * angle.cos()).abs() < 1e-5, "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn losing_every_life_ends_the_game() {
+        let mut breakout = breakout();
+        for lives in (1..BREAKOUT_LIVES).rev() {
+            breakout.serve();
+            breakout.ball = (0.9, GROUND_TOP + BALL_RADIUS / 2.0);
+            breakout.ball_velocity = (0.0, -1.0);
+            breakout.step(0.001);
+            assert_eq!(breakout.lives, lives);
+            assert!(breakout.phase == Phase::Serving);
+            assert_eq!(breakout.ball_velocity, (0.0, 0.0));
+        }
+        assert!(breakout
+            .sounds
+            .iter()
+            .all(|&sound| sound == Sound::LifeLost));
+
+        breakout.serve();
+        breakout.ball = (0.9, GROUND_TOP + BALL_RADIUS / 2.0);
+        breakout.step(0.001);
+        assert_eq!(breakout.lives, 0);
+        assert!(breakout.phase == Phase::GameOver);
+        assert_eq!(breakout.sounds.last(), Some(&Sound::GameOver));
+        assert!(breakout
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::GameOver { stage: 1, .. })));
+
+        // The ball stays put until the player restarts
+        breakout.serve();
+        assert!(breakout.phase == Phase::GameOver);
+    }
+
+    #[test]
+    fn serves_again_after_restart() {
+        let storage = MemoryStorage::default();
+        let mut breakout = breakout();
+        breakout.screen_filter = ScreenFilter::None.next();
+        breakout.lives = 1;
+        breakout.serve();
+        breakout.lose_life();
+        assert!(breakout.phase == Phase::GameOver);
+
+        breakout.handle_action(&storage, Action::Fire, true);
+        assert!(breakout.phase == Phase::Serving);
+        assert_eq!(breakout.lives, BREAKOUT_LIVES);
+        assert_eq!(breakout.score, 0);
+        assert!(breakout.screen_filter == ScreenFilter::None.next());
+
+        breakout.handle_action(&storage, Action::Fire, false);
+        breakout.handle_action(&storage, Action::Fire, true);
+        assert!(breakout.phase == Phase::Playing);
+        let (dx, dy) = breakout.ball_velocity;
+        assert!(dy > 0.0);
+        assert!(((dx * dx + dy * dy).sqrt() - breakout.ball_speed()).abs() < 1e-5);
+    }
+}
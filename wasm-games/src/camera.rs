@@ -0,0 +1,145 @@
+// ### Camera
+
+/// How the world is fitted to a screen of a different aspect ratio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FitMode {
+    /// Shows the whole world with bars along the spare edges.
+    Letterbox,
+    /// Shows the whole world and whatever lies beyond it along the spare edges.
+    Expand,
+    /// Fills the screen, cutting off the edges of the world that don't fit.
+    Crop,
+}
+
+impl FitMode {
+    pub const ALL: [FitMode; 3] = [FitMode::Letterbox, FitMode::Expand, FitMode::Crop];
+
+    /// Name used by the page to pick the mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            FitMode::Letterbox => "letterbox",
+            FitMode::Expand => "expand",
+            FitMode::Crop => "crop",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<FitMode> {
+        FitMode::ALL.iter().copied().find(|fit| fit.name() == name)
+    }
+}
+
+/// Maps a world of `world_width` by `world_height` units centred on the origin onto the canvas
+/// without stretching it, and maps canvas positions back into the world.
+pub struct Camera {
+    world_width: f32,
+    world_height: f32,
+    fit: FitMode,
+    screen_width: f32, // Canvas size in pixels
+    screen_height: f32,
+    shake: (f32, f32), // Offset of the view in world units, for screen shake
+}
+
+impl Camera {
+    pub fn new(world_width: f32, world_height: f32, fit: FitMode) -> Self {
+        Camera {
+            world_width,
+            world_height,
+            fit,
+            screen_width: world_width,
+            screen_height: world_height,
+            shake: (0.0, 0.0),
+        }
+    }
+
+    /// Fits a world of `world_width` by `world_height` units from now on.
+    pub fn set_world(&mut self, world_width: f32, world_height: f32, fit: FitMode) {
+        self.world_width = world_width;
+        self.world_height = world_height;
+        self.fit = fit;
+    }
+
+    pub fn resize(&mut self, screen_width: u32, screen_height: u32) {
+        // Keep a usable mapping while the canvas is collapsed
+        self.screen_width = screen_width.max(1) as f32;
+        self.screen_height = screen_height.max(1) as f32;
+    }
+
+    pub fn screen_size(&self) -> (u32, u32) {
+        (self.screen_width as u32, self.screen_height as u32)
+    }
+
+    /// Offsets what is drawn by (`x`, `y`) world units. Input mapping is not affected.
+    pub fn set_shake(&mut self, x: f32, y: f32) {
+        self.shake = (x, y);
+    }
+
+    /// Pixels per world unit.
+    pub fn scale(&self) -> f32 {
+        let scale_x = self.screen_width / self.world_width;
+        let scale_y = self.screen_height / self.world_height;
+        match self.fit {
+            FitMode::Letterbox | FitMode::Expand => scale_x.min(scale_y),
+            FitMode::Crop => scale_x.max(scale_y),
+        }
+    }
+
+    /// Half the width and height of the area shown by the viewport, in world units.
+    fn half_extents(&self) -> (f32, f32) {
+        match self.fit {
+            FitMode::Letterbox => (self.world_width / 2.0, self.world_height / 2.0),
+            FitMode::Expand | FitMode::Crop => {
+                let scale = self.scale();
+                (
+                    self.screen_width / scale / 2.0,
+                    self.screen_height / scale / 2.0,
+                )
+            }
+        }
+    }
+
+    /// Left, bottom, right and top of the area shown on screen, in world units.
+    pub fn visible_bounds(&self) -> (f32, f32, f32, f32) {
+        let (half_width, half_height) = self.half_extents();
+        (-half_width, -half_height, half_width, half_height)
+    }
+
+    /// Part of the canvas drawn to, as x, y, width and height in pixels from the bottom-left.
+    pub fn viewport(&self) -> (i32, i32, i32, i32) {
+        match self.fit {
+            FitMode::Letterbox => {
+                let scale = self.scale();
+                let width = self.world_width * scale;
+                let height = self.world_height * scale;
+                (
+                    ((self.screen_width - width) / 2.0).round() as i32,
+                    ((self.screen_height - height) / 2.0).round() as i32,
+                    width.round() as i32,
+                    height.round() as i32,
+                )
+            }
+            FitMode::Expand | FitMode::Crop => {
+                (0, 0, self.screen_width as i32, self.screen_height as i32)
+            }
+        }
+    }
+
+    /// Column-major orthographic projection from world units to clip space over the viewport.
+    pub fn projection(&self) -> [f32; 16] {
+        let (half_width, half_height) = self.half_extents();
+        let (scale_x, scale_y) = (1.0 / half_width, 1.0 / half_height);
+        let (offset_x, offset_y) = (self.shake.0 * scale_x, self.shake.1 * scale_y);
+        [
+            scale_x, 0.0, 0.0, 0.0, 0.0, scale_y, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, offset_x, offset_y,
+            0.0, 1.0,
+        ]
+    }
+
+    /// World position under a canvas position given in pixels from the top-left corner.
+    pub fn unproject(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
+        let scale = self.scale();
+        (
+            (screen_x - self.screen_width / 2.0) / scale,
+            (self.screen_height / 2.0 - screen_y) / scale,
+        )
+    }
+}
@@ -0,0 +1,1199 @@
+use std::collections::HashSet;
+use winit::event::{MouseButton, TouchPhase, VirtualKeyCode};
+
+use crate::atlas::Sprite;
+use crate::audio::Sound;
+use crate::camera::Camera;
+use crate::config::GameConfig;
+use crate::events::GameEvent;
+use crate::game::{BindingRequest, Engine, Game, GameInput, GameSetup, Setting, TouchRequest};
+use crate::gamepad::{PadEvent, PadInput};
+use crate::input::{Action, InputMap};
+use crate::mouse::PointerLock;
+use crate::particles::{Emission, EmitterDef, ParticleSystem};
+use crate::player::{barrel_tip, Player, BASE_HEIGHT, BASE_WIDTH, CANNON_LENGTH, CANNON_WIDTH};
+use crate::playfield::{
+    build_stage, draw_bricks, draw_message, draw_playfield, hud_bounds, Brick, HashGrid,
+    BRICK_DEBRIS, FLASH_DECAY, FLASH_PER_BRICK, FULL_CIRCLE, GROUND_TOP, HIT_SPARKS, HUD_TEXT_SIZE,
+    MAX_PARTICLES, MESSAGE_DURATION, SHAKE_DECAY, SHAKE_PER_BRICK, SHAKE_PER_HIT,
+};
+use crate::powerups::{PowerUpKind, PowerUps};
+use crate::random::random;
+use crate::renderer::{PostEffects, Renderer, ScreenFilter};
+use crate::shop::{ShopAction, ShopPanel};
+use crate::storage::Storage;
+use crate::text::{self, Align, TextStyle};
+use crate::theme::{saved_theme, Theme, THEME_KEY};
+use crate::touch::{TouchControls, TouchScheme};
+use crate::upgrades::{Progress, UpgradeStat};
+use crate::weapons::{Bullet, Weapon};
+
+// ### Constants
+
+const AMMO_PICKUP_ROUNDS: u32 = 100;
+const PICKUP_SIZE: f32 = 0.04;
+const PICKUP_FALL_SPEED: f32 = 0.3;
+const AMMO_PICKUP_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0]; // Yellow ammo crates
+const MULTIBALL_ANGLE: f32 = 0.2; // Angle between split bullets in radians
+const MULTIBALL_MAX_BULLETS: usize = 600; // Multiball stops splitting with this many in flight
+const PIERCE_COUNT: u32 = 3; // Bricks a piercing bullet passes through
+const TOUCH_SCHEME: TouchScheme = TouchScheme::DragToAim;
+const METER_WIDTH: f32 = 0.4; // HUD meter width
+const METER_HEIGHT: f32 = 0.015; // HUD meter height
+const CROSSHAIR_SIZE: f32 = 0.03;
+const BINDINGS_KEY: &str = "brick-breaker-bindings"; // Storage key of single-player controls
+const COOP_BINDINGS_KEY: &str = "brick-breaker-bindings-coop";
+
+// ### Particle Effects
+
+/// Sparks from the barrel along the shot, aimed and coloured by the theme when emitted.
+const MUZZLE_FLASH: EmitterDef = EmitterDef {
+    emission: Emission::Burst(5),
+    direction: 0.0,
+    spread: 0.8,
+    speed: (0.2, 0.5),
+    lifetime: (0.1, 0.25),
+    gravity: 0.0,
+    drag: 6.0,
+    size: (0.008, 0.002),
+    color: ([1.0, 0.7, 0.2, 1.0], [1.0, 0.2, 0.0, 0.0]),
+    additive: true,
+};
+
+/// Glitter around a collected pickup, tinted with the pickup colour.
+const PICKUP_SPARKLE: EmitterDef = EmitterDef {
+    emission: Emission::Burst(10),
+    direction: 0.0,
+    spread: FULL_CIRCLE,
+    speed: (0.1, 0.3),
+    lifetime: (0.3, 0.6),
+    gravity: 0.0,
+    drag: 3.0,
+    size: (0.004, 0.008),
+    color: ([1.0; 4], [1.0, 1.0, 1.0, 0.0]),
+    additive: true,
+};
+
+/// Smoke rising from an overheated barrel for as long as it stays locked, coloured by the theme.
+const OVERHEAT_SMOKE: EmitterDef = EmitterDef {
+    emission: Emission::Continuous(25.0),
+    direction: std::f32::consts::FRAC_PI_2,
+    spread: 0.6,
+    speed: (0.05, 0.15),
+    lifetime: (0.6, 1.0),
+    gravity: -0.05,
+    drag: 0.5,
+    size: (0.008, 0.025),
+    color: ([0.5, 0.5, 0.5, 0.6], [0.3, 0.3, 0.3, 0.0]),
+    additive: false,
+};
+
+// ### Structures
+
+/// Kinds of items that fall from destroyed bricks.
+enum PickupKind {
+    Ammo(u32),
+    PowerUp(PowerUpKind),
+}
+
+impl PickupKind {
+    fn color(&self) -> [f32; 4] {
+        match self {
+            PickupKind::Ammo(_) => AMMO_PICKUP_COLOR,
+            PickupKind::PowerUp(kind) => kind.color(),
+        }
+    }
+}
+
+/// Represents an item falling towards the ground, collected by the cannon or by shooting it.
+struct Pickup {
+    x: f32,
+    y: f32,
+    dy: f32,
+    kind: PickupKind,
+}
+
+// ### Game State
+
+/// Whether the game is being played or waiting in the shop between stages.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Playing,
+    Shop,
+    GameOver, // Every weapon ran dry with bricks left
+}
+
+/// Number of cannons sharing the screen.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Single,
+    /// Two players on one keyboard or touch screen, each with half of the screen.
+    CoOp,
+}
+
+struct GameState {
+    mode: GameMode,
+    config: GameConfig,
+    players: Vec<Player>,
+    bullets: Vec<Bullet>,
+    bricks: Vec<Brick>,
+    particles: ParticleSystem,
+    pickups: Vec<Pickup>,
+    power_ups: PowerUps,
+    hash_grid: HashGrid,
+    message: Option<(String, f32)>, // Centred message and its remaining time
+    trauma: f32,                    // Screen shake from impacts, 0..1
+    flash: f32,                     // Chromatic flash from destroyed bricks, 0..1
+    screen_filter: ScreenFilter,
+    theme: &'static Theme,
+    cursor: Option<(f32, f32)>, // Mouse position player one aims at, while over the canvas
+    show_crosshair: bool,
+    pointer_lock: bool, // Whether clicking locks the pointer for relative aiming
+    input: InputMap,
+    touch: TouchControls,
+    bindings_key: &'static str, // Storage key of `input`, which differs per mode
+    stage: u32,
+    phase: Phase,
+    progress: Progress,
+    reported_scores: Vec<u32>, // Scores and weapons as last reported to the page
+    reported_weapons: Vec<&'static str>,
+}
+
+impl GameState {
+    fn new(mode: GameMode, config: GameConfig, storage: &dyn Storage) -> Self {
+        let progress = Progress::load(storage);
+        let theme = saved_theme(storage);
+        let [first_color, second_color] = theme.players;
+        let mut players = match mode {
+            GameMode::Single => vec![Player::new(0.0, -1.0, 1.0, first_color, &config)],
+            GameMode::CoOp => vec![
+                Player::new(-0.5, -1.0, 0.0, first_color, &config),
+                Player::new(0.5, 0.0, 1.0, second_color, &config),
+            ],
+        };
+        let bindings_key = match mode {
+            GameMode::Single => BINDINGS_KEY,
+            GameMode::CoOp => COOP_BINDINGS_KEY,
+        };
+        let input = InputMap::load(storage, bindings_key, players.len());
+        for player in &mut players {
+            player.apply_upgrades(&progress);
+        }
+        let (bricks, hash_grid) = build_stage(1, &config);
+
+        GameState {
+            mode,
+            config,
+            players,
+            bullets: Vec::new(),
+            bricks,
+            particles: ParticleSystem::new(MAX_PARTICLES),
+            pickups: Vec::new(),
+            power_ups: PowerUps::default(),
+            hash_grid,
+            message: Some(("Stage 1".to_string(), MESSAGE_DURATION)),
+            trauma: 0.0,
+            flash: 0.0,
+            screen_filter: ScreenFilter::None,
+            theme,
+            cursor: None,
+            show_crosshair: config.show_crosshair,
+            pointer_lock: config.pointer_lock,
+            input,
+            touch: TouchControls::new(TOUCH_SCHEME),
+            bindings_key,
+            stage: 1,
+            phase: Phase::Playing,
+            progress,
+            reported_scores: Vec::new(),
+            reported_weapons: Vec::new(),
+        }
+    }
+
+    /// Starts over from stage 1 in the same mode. Upgrades, coins earned in the unfinished
+    /// stage and settings carry over.
+    fn restart(&mut self, storage: &mut dyn Storage) {
+        self.progress.save(storage);
+        let mut state = GameState::new(self.mode, self.config, storage);
+        state.screen_filter = self.screen_filter;
+        state.show_crosshair = self.show_crosshair;
+        state.pointer_lock = self.pointer_lock;
+        state.touch = self.touch.restarted();
+        *self = state;
+    }
+
+    /// Queues an event for every score or weapon that changed since the last call.
+    fn report_changes(&mut self, events: &mut Vec<GameEvent>) {
+        for (index, player) in self.players.iter().enumerate() {
+            if self.reported_scores.get(index) != Some(&player.score) {
+                events.push(GameEvent::ScoreChanged {
+                    player: index,
+                    score: player.score,
+                });
+            }
+            if self.reported_weapons.get(index) != Some(&player.weapon.name()) {
+                events.push(GameEvent::WeaponChanged {
+                    player: index,
+                    weapon: player.weapon.name(),
+                });
+            }
+        }
+        self.reported_scores = self.players.iter().map(|player| player.score).collect();
+        self.reported_weapons = self
+            .players
+            .iter()
+            .map(|player| player.weapon.name())
+            .collect();
+    }
+
+    /// Leaves the shop and starts the next stage with a fresh brick layout.
+    fn next_stage(&mut self) {
+        self.stage += 1;
+        let (bricks, hash_grid) = build_stage(self.stage, &self.config);
+        self.bricks = bricks;
+        self.hash_grid = hash_grid;
+        self.bullets.clear();
+        self.pickups.clear();
+        for player in &mut self.players {
+            player.apply_upgrades(&self.progress);
+        }
+        self.phase = Phase::Playing;
+        self.show_message(format!("Stage {}", self.stage));
+    }
+
+    /// Switches to `theme` and remembers it for the next session.
+    fn set_theme(&mut self, theme: &'static Theme, storage: &mut dyn Storage) {
+        self.theme = theme;
+        for (player, &color) in self.players.iter_mut().zip(theme.players.iter()) {
+            player.color = color;
+        }
+        storage.save(THEME_KEY, theme.name);
+    }
+
+    fn show_message(&mut self, text: String) {
+        self.message = Some((text, MESSAGE_DURATION));
+    }
+
+    fn handle_shop_action(&mut self, action: ShopAction, storage: &mut dyn Storage) {
+        match action {
+            ShopAction::Buy(index) => {
+                if self.progress.purchase(index) {
+                    self.progress.save(storage);
+                }
+            }
+            ShopAction::Continue => self.next_stage(),
+        }
+    }
+
+    /// Player and action of every binding for `keycode`.
+    fn actions_for_key(&self, keycode: VirtualKeyCode) -> Vec<(usize, Action)> {
+        // Bindings name keys after the VirtualKeyCode variants
+        let key = format!("{:?}", keycode);
+        self.input.actions_for(&key).collect()
+    }
+
+    /// Changes the controls and remembers them for the next session.
+    fn rebind(&mut self, request: BindingRequest, storage: &mut dyn Storage) {
+        match request {
+            BindingRequest::Bind(player, action, key) => {
+                if !self.input.bind(player, action, &key) {
+                    log::warn!("Cannot bind {} for player {}", key, player);
+                }
+            }
+            BindingRequest::Clear(player, action) => self.input.clear(player, action),
+            BindingRequest::Reset => self.input = InputMap::defaults(self.players.len()),
+        }
+        // Release everything so a rebound key can't leave an action stuck on
+        for player in &mut self.players {
+            for &action in Action::ALL.iter() {
+                player.set_action(action, false);
+            }
+        }
+        self.input.save(storage, self.bindings_key);
+    }
+
+    /// Aims player one's barrel at the mouse, kept within what the camera shows.
+    fn cursor_moved(&mut self, x: f32, y: f32, camera: &Camera) {
+        let (left, bottom, right, top) = camera.visible_bounds();
+        let (x, y) = (x.clamp(left, right), y.clamp(bottom, top));
+        self.cursor = Some((x, y));
+        self.players[0].aim_at(x, y);
+    }
+
+    /// Moves the mouse aim by (`dx`, `dy`) world units, for relative aiming under pointer lock.
+    fn cursor_moved_by(&mut self, dx: f32, dy: f32, camera: &Camera) {
+        let (x, y) = self.cursor.unwrap_or((0.0, 0.0));
+        self.cursor_moved(x + dx, y + dy, camera);
+    }
+
+    /// Feeds polled gamepads to the players they belong to, the first pad to player one.
+    fn apply_gamepads(&mut self, pads: &[PadInput]) {
+        for (index, player) in self.players.iter_mut().enumerate() {
+            let pad = pads.get(index);
+            player.drive_axis = pad.map_or(0.0, |pad| pad.drive);
+            player.aim_axis = pad.map_or(0.0, |pad| pad.aim);
+            // Triggers only report changes, so they don't override held keys
+            if let Some(fire) = pad.and_then(|pad| pad.fire) {
+                player.set_action(Action::Fire, fire);
+            }
+            if let Some(forward) = pad.and_then(|pad| pad.switch_weapon) {
+                let action = if forward {
+                    Action::NextWeapon
+                } else {
+                    Action::PreviousWeapon
+                };
+                player.set_action(action, true);
+            }
+        }
+    }
+
+    fn gamepad_event(&mut self, event: PadEvent) {
+        let text = match event {
+            PadEvent::Connected(id) => {
+                log::info!("Gamepad connected: {}", id);
+                "Gamepad connected"
+            }
+            PadEvent::Disconnected(id) => {
+                log::info!("Gamepad disconnected: {}", id);
+                "Gamepad disconnected"
+            }
+        };
+        self.show_message(text.to_string());
+    }
+
+    fn touch_started(&mut self, id: u64, x: f32, y: f32) {
+        self.touch.started(id, x, y, &mut self.players);
+    }
+
+    fn touch_moved(&mut self, id: u64, x: f32, y: f32) {
+        self.touch.moved(id, x, y, &mut self.players);
+    }
+
+    fn touch_ended(&mut self, id: u64) {
+        self.touch.ended(id, &mut self.players);
+    }
+
+    fn configure_touch(&mut self, request: TouchRequest) {
+        match request {
+            TouchRequest::Scheme(scheme) => self.touch.set_scheme(scheme, &mut self.players),
+            TouchRequest::DeadZones { joystick, drag } => {
+                self.touch.joystick_dead_zone = joystick;
+                self.touch.drag_dead_zone = drag;
+            }
+        }
+    }
+
+    /// Score, coins and stage shown in the top-left corner.
+    fn hud_text(&self) -> String {
+        let mut text = String::new();
+        if self.players.len() == 1 {
+            text.push_str(&format!("Score {}\n", self.players[0].score));
+        } else {
+            for (index, player) in self.players.iter().enumerate() {
+                text.push_str(&format!("P{} {}\n", index + 1, player.score));
+            }
+        }
+        text.push_str(&format!(
+            "Coins {}\nStage {}",
+            self.progress.currency, self.stage
+        ));
+        text
+    }
+
+    /// Camera offset for this frame's screen shake, growing with the square of the trauma.
+    fn shake_offset(&self) -> (f32, f32) {
+        let amount = self.config.max_shake * self.trauma * self.trauma;
+        (
+            (random() as f32 * 2.0 - 1.0) * amount,
+            (random() as f32 * 2.0 - 1.0) * amount,
+        )
+    }
+
+    fn post_effects(&self) -> PostEffects {
+        PostEffects {
+            bloom: self.config.bloom_strength,
+            filter: self.screen_filter,
+            chromatic: self.flash,
+        }
+    }
+
+    fn update(&mut self, engine: &mut Engine, delta_time: f32) {
+        if let Some((_, remaining)) = &mut self.message {
+            *remaining -= delta_time;
+            if *remaining <= 0.0 {
+                self.message = None;
+            }
+        }
+        self.trauma = (self.trauma - SHAKE_DECAY * delta_time).max(0.0);
+        self.flash = (self.flash - FLASH_DECAY * delta_time).max(0.0);
+        self.report_changes(&mut engine.events);
+        self.particles.set_speed_scale(self.config.particle_speed);
+        if self.phase != Phase::Playing {
+            return;
+        }
+
+        // Move and aim the cannons
+        let rotation_speed =
+            self.config.rotation_speed * (1.0 + self.progress.bonus(UpgradeStat::RotationSpeed));
+        self.touch.update(&mut self.players);
+        for player in &mut self.players {
+            player.rotate(rotation_speed, delta_time);
+            player.drive(&self.config, delta_time);
+        }
+
+        // Power-ups run on real time; slow-motion only affects the world
+        self.power_ups.update(delta_time);
+        let world_delta = if self.power_ups.is_active(PowerUpKind::SlowMotion) {
+            delta_time * self.config.slow_motion_factor
+        } else {
+            delta_time
+        };
+
+        // Update weapons and fire bullets
+        let fire_rate_multiplier = (1.0 + self.power_ups.stacks(PowerUpKind::RapidFire) as f32)
+            * (1.0 + self.progress.bonus(UpgradeStat::FireRate));
+        let mut to_spawn = Vec::new();
+        for (index, player) in self.players.iter_mut().enumerate() {
+            player.update_weapons(fire_rate_multiplier, delta_time);
+            if player.weapon.heat().is_some_and(|heat| heat.overheated) {
+                let (tip_x, tip_y) = barrel_tip(player.cannon_x, player.cannon_y, player.theta);
+                let mut smoke = OVERHEAT_SMOKE;
+                smoke.color = (self.theme.smoke[0], self.theme.smoke[1]);
+                to_spawn.push((smoke, tip_x, tip_y));
+            }
+            if !player.fire_pressed {
+                continue;
+            }
+            if let Some(mut bullet) = player
+                .weapon
+                .try_fire((player.cannon_x, player.cannon_y), player.theta)
+            {
+                bullet.owner = index;
+                bullet.dx *= self.config.ball_speed;
+                bullet.dy *= self.config.ball_speed;
+                if self.power_ups.is_active(PowerUpKind::Piercing) {
+                    bullet.pierce = PIERCE_COUNT;
+                }
+                if self.power_ups.is_active(PowerUpKind::Multiball)
+                    && self.bullets.len() + 3 <= MULTIBALL_MAX_BULLETS
+                {
+                    self.bullets.push(bullet.split(MULTIBALL_ANGLE));
+                    self.bullets.push(bullet.split(-MULTIBALL_ANGLE));
+                }
+                self.bullets.push(bullet);
+                let (tip_x, tip_y) = barrel_tip(player.cannon_x, player.cannon_y, player.theta);
+                let mut flash = MUZZLE_FLASH;
+                flash.direction = player.theta + std::f32::consts::FRAC_PI_2;
+                flash.color = (self.theme.muzzle_flash[0], self.theme.muzzle_flash[1]);
+                to_spawn.push((flash, tip_x, tip_y));
+            }
+        }
+
+        // Update bullets and handle collisions
+        let mut to_drop = Vec::new();
+        let mut collected = Vec::new();
+        let mut currency_earned = 0;
+        let shield_active = self.power_ups.is_active(PowerUpKind::Shield);
+        let friction_factor = self.config.friction.powf(world_delta);
+        for bullet in &mut self.bullets {
+            bullet.dx *= friction_factor;
+            bullet.dy *= friction_factor;
+
+            bullet.x += bullet.dx * world_delta;
+            bullet.y += bullet.dy * world_delta;
+
+            // Bounce off walls
+            if bullet.x - bullet.radius < -1.0 {
+                bullet.x = -1.0 + bullet.radius;
+                bullet.dx = -bullet.dx;
+            }
+            if bullet.x + bullet.radius > 1.0 {
+                bullet.x = 1.0 - bullet.radius;
+                bullet.dx = -bullet.dx;
+            }
+            if bullet.y + bullet.radius > 1.0 {
+                bullet.y = 1.0 - bullet.radius;
+                bullet.dy = -bullet.dy;
+            }
+            if shield_active && bullet.y - bullet.radius < GROUND_TOP {
+                bullet.y = GROUND_TOP + bullet.radius;
+                bullet.dy = bullet.dy.abs();
+            }
+
+            let bullet_left = bullet.x - bullet.radius;
+            let bullet_right = bullet.x + bullet.radius;
+            let bullet_bottom = bullet.y - bullet.radius;
+            let bullet_top = bullet.y + bullet.radius;
+
+            let (i_x_min, i_y_min) = self.hash_grid.get_cell_index(bullet_left, bullet_bottom);
+            let (i_x_max, i_y_max) = self.hash_grid.get_cell_index(bullet_right, bullet_top);
+
+            let mut bricks_to_check = HashSet::new();
+            for i_x in i_x_min..=i_x_max {
+                for i_y in i_y_min..=i_y_max {
+                    let bricks_in_cell = self.hash_grid.get_bricks_in_cell(i_x, i_y);
+                    for &brick_index in bricks_in_cell {
+                        bricks_to_check.insert(brick_index);
+                    }
+                }
+            }
+
+            for brick_index in bricks_to_check {
+                let brick = &mut self.bricks[brick_index];
+                if brick.health > 0
+                    && bullet.health > 0
+                    && bullet.last_hit != Some(brick_index)
+                    && bullet_right > brick.left
+                    && bullet_left < brick.right
+                    && bullet_top > brick.bottom
+                    && bullet_bottom < brick.top
+                {
+                    let color = brick.color(self.theme);
+                    brick.health = brick.health.saturating_sub(bullet.damage);
+                    bullet.last_hit = Some(brick_index);
+                    if bullet.pierce > 0 {
+                        bullet.pierce -= 1;
+                    } else {
+                        bullet.health -= 1;
+                        bullet.dy = -bullet.dy;
+                    }
+                    brick.hit_timer = self.config.hit_effect_duration;
+                    to_spawn.push((HIT_SPARKS.tinted(color), bullet.x, bullet.y));
+                    self.trauma = (self.trauma + SHAKE_PER_HIT).min(1.0);
+                    if brick.health == 0 {
+                        self.trauma = (self.trauma + SHAKE_PER_BRICK).min(1.0);
+                        self.flash = (self.flash + FLASH_PER_BRICK).min(1.0);
+                        self.players[bullet.owner].score += 1;
+                        currency_earned += self.stage;
+                        let brick_center_x = (brick.left + brick.right) / 2.0;
+                        let brick_center_y = (brick.bottom + brick.top) / 2.0;
+                        engine.audio.play(Sound::BrickBroken);
+                        engine.events.push(GameEvent::BrickDestroyed {
+                            player: bullet.owner,
+                            x: brick_center_x,
+                            y: brick_center_y,
+                        });
+                        to_spawn.push((BRICK_DEBRIS.tinted(color), brick_center_x, brick_center_y));
+                        to_drop.push((brick_center_x, brick_center_y));
+                    }
+                }
+            }
+
+            // Shooting a pickup collects it
+            if bullet.health > 0 {
+                let mut i = 0;
+                while i < self.pickups.len() {
+                    let pickup = &self.pickups[i];
+                    if (bullet.x - pickup.x).abs() < PICKUP_SIZE / 2.0 + bullet.radius
+                        && (bullet.y - pickup.y).abs() < PICKUP_SIZE / 2.0 + bullet.radius
+                    {
+                        bullet.health -= 1;
+                        collected.push((bullet.owner, self.pickups.swap_remove(i)));
+                        break;
+                    }
+                    i += 1;
+                }
+            }
+        }
+
+        // Coins are saved when the stage ends rather than on every brick
+        self.progress.currency += currency_earned;
+
+        // Destroyed bricks may drop ammo, if weapons use it, or a power-up
+        for (x, y) in to_drop {
+            let roll = random() as f32;
+            let ammo_chance = if self.config.finite_ammo {
+                self.config.ammo_drop_chance
+            } else {
+                0.0
+            };
+            let kind = if roll < ammo_chance {
+                PickupKind::Ammo(AMMO_PICKUP_ROUNDS)
+            } else if roll < ammo_chance + self.config.power_up_drop_chance {
+                PickupKind::PowerUp(PowerUpKind::random())
+            } else {
+                continue;
+            };
+            self.pickups.push(Pickup {
+                x,
+                y,
+                dy: -PICKUP_FALL_SPEED,
+                kind,
+            });
+        }
+
+        // Update falling pickups; the cannon bases catch them
+        let mut i = 0;
+        while i < self.pickups.len() {
+            let pickup = &mut self.pickups[i];
+            pickup.y += pickup.dy * world_delta;
+            let catcher = self.players.iter().position(|player| {
+                (pickup.x - player.cannon_x).abs() < (BASE_WIDTH + PICKUP_SIZE) / 2.0
+                    && (pickup.y - player.cannon_y).abs() < (BASE_HEIGHT + PICKUP_SIZE) / 2.0
+            });
+            if let Some(catcher) = catcher {
+                collected.push((catcher, self.pickups.swap_remove(i)));
+            } else if pickup.y - PICKUP_SIZE / 2.0 < GROUND_TOP {
+                self.pickups.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        for (collector, pickup) in collected {
+            engine.audio.play(Sound::Pickup);
+            match pickup.kind {
+                PickupKind::Ammo(rounds) => self.players[collector].weapon.add_ammo(rounds),
+                PickupKind::PowerUp(kind) => {
+                    if kind == PowerUpKind::Multiball {
+                        split_bullets(&mut self.bullets);
+                    }
+                    self.power_ups.activate(kind);
+                    self.show_message(format!("{}!", kind.name()));
+                }
+            }
+            let sparkle = PICKUP_SPARKLE.tinted(pickup.kind.color());
+            self.particles
+                .emit(&sparkle, pickup.x, pickup.y, world_delta);
+        }
+
+        // Spawn particles from shots and collisions
+        for (def, x, y) in to_spawn {
+            self.particles.emit(&def, x, y, world_delta);
+        }
+
+        // Remove dead bullets
+        self.bullets
+            .retain(|bullet| bullet.health > 0 && bullet.y - bullet.radius >= -1.0);
+
+        // Update brick hit timers
+        for brick in &mut self.bricks {
+            if brick.hit_timer > 0.0 {
+                brick.hit_timer -= world_delta;
+                if brick.hit_timer < 0.0 {
+                    brick.hit_timer = 0.0;
+                }
+            }
+        }
+
+        self.particles.update(world_delta);
+
+        // Open the shop once every brick is destroyed
+        if self.bricks.iter().all(|brick| brick.health == 0) {
+            self.phase = Phase::Shop;
+            for player in &mut self.players {
+                player.fire_pressed = false;
+            }
+            self.progress.save(engine.storage.as_mut());
+            engine.audio.play(Sound::StageCleared);
+            engine
+                .events
+                .push(GameEvent::StageCleared { stage: self.stage });
+        } else if self.bullets.is_empty()
+            && self.pickups.is_empty()
+            && self.players.iter().all(Player::is_out_of_ammo)
+        {
+            self.phase = Phase::GameOver;
+            self.progress.save(engine.storage.as_mut());
+            self.message = Some((
+                "Game over\nPress Enter to play again".to_string(),
+                f32::INFINITY,
+            ));
+            engine.audio.play(Sound::GameOver);
+            engine.events.push(GameEvent::GameOver {
+                stage: self.stage,
+                scores: self.players.iter().map(|player| player.score).collect(),
+            });
+        }
+    }
+}
+
+// ### Cannon Game
+
+/// The brick-breaker cannon game, with the page UI it drives besides the canvas: the shop
+/// panel and pointer lock.
+pub struct CannonGame {
+    state: GameState,
+    canvas: web_sys::HtmlCanvasElement,
+    shop_panel: ShopPanel,
+    shop_visible: bool,
+    shop_dirty: bool, // Prices changed while the shop is open
+    pointer_lock: PointerLock,
+}
+
+impl CannonGame {
+    pub fn new(mode: GameMode, setup: GameSetup) -> Self {
+        let state = GameState::new(mode, setup.config, setup.storage);
+        set_cursor_style(setup.canvas, state.show_crosshair);
+        CannonGame {
+            state,
+            canvas: setup.canvas.clone(),
+            shop_panel: ShopPanel::new(setup.container),
+            shop_visible: false,
+            shop_dirty: false,
+            pointer_lock: PointerLock::new(setup.canvas.clone()),
+        }
+    }
+
+    fn set_crosshair(&mut self, show: bool) {
+        self.state.show_crosshair = show;
+        set_cursor_style(&self.canvas, show);
+    }
+
+    /// Turns relative aiming on or off. Turning it on takes effect from the next click.
+    fn set_pointer_lock(&mut self, lock: bool) {
+        self.state.pointer_lock = lock;
+        if !lock {
+            self.pointer_lock.release();
+        }
+    }
+
+    fn handle_action(&mut self, engine: &mut Engine, player: usize, action: Action, pressed: bool) {
+        let state = &mut self.state;
+        match action {
+            Action::ToggleCrosshair if pressed => {
+                let show = !state.show_crosshair;
+                self.set_crosshair(show);
+            }
+            Action::TogglePointerLock if pressed => {
+                let lock = !state.pointer_lock;
+                self.set_pointer_lock(lock);
+                if lock {
+                    self.pointer_lock.request();
+                }
+            }
+            Action::CycleFilter if pressed => {
+                state.screen_filter = state.screen_filter.next();
+            }
+            Action::Buy(index) => {
+                if pressed && state.phase == Phase::Shop {
+                    state.handle_shop_action(ShopAction::Buy(index), engine.storage.as_mut());
+                    self.shop_dirty = true;
+                }
+            }
+            Action::Continue if pressed => match state.phase {
+                Phase::Shop => {
+                    state.handle_shop_action(ShopAction::Continue, engine.storage.as_mut())
+                }
+                Phase::GameOver => self.restart(engine),
+                Phase::Playing => {}
+            },
+            _ => {
+                state.players[player].set_action(action, pressed);
+            }
+        }
+    }
+}
+
+impl Game for CannonGame {
+    fn update(&mut self, engine: &mut Engine, delta_time: f32) {
+        let (move_x, move_y) = self.pointer_lock.take_movement();
+        if move_x != 0.0 || move_y != 0.0 {
+            // CSS pixels to world units, with y pointing up
+            let units = engine.world_units_per_css_pixel();
+            self.state.cursor_moved_by(
+                move_x as f32 * units,
+                -move_y as f32 * units,
+                &engine.camera,
+            );
+        }
+        for action in self.shop_panel.take_actions() {
+            self.state
+                .handle_shop_action(action, engine.storage.as_mut());
+            self.shop_dirty = true;
+        }
+        self.state.update(engine, delta_time);
+        match self.state.phase {
+            Phase::Shop if !self.shop_visible || self.shop_dirty => {
+                // The shop needs the cursor back
+                self.pointer_lock.release();
+                self.shop_panel.show(&self.state.progress, self.state.stage);
+                self.shop_visible = true;
+            }
+            Phase::Playing if self.shop_visible => {
+                self.shop_panel.hide();
+                self.shop_visible = false;
+            }
+            _ => {}
+        }
+        self.shop_dirty = false;
+    }
+
+    fn render(&self, renderer: &mut dyn Renderer, camera: &mut Camera) {
+        let (shake_x, shake_y) = self.state.shake_offset();
+        camera.set_shake(shake_x, shake_y);
+        renderer.set_camera(camera);
+        renderer.set_effects(self.state.post_effects());
+        renderer.clear(self.state.theme.clear);
+        draw_game(renderer, &self.state, camera);
+    }
+
+    fn handle_input(&mut self, engine: &mut Engine, input: GameInput) {
+        match input {
+            GameInput::Key { key, pressed } => {
+                for (player, action) in self.state.actions_for_key(key) {
+                    self.handle_action(engine, player, action, pressed);
+                }
+            }
+            GameInput::CursorMoved { x, y } => {
+                // Under pointer lock the position is frozen; movement is summed instead
+                if !self.pointer_lock.is_locked() {
+                    self.state.cursor_moved(x, y, &engine.camera);
+                }
+            }
+            GameInput::CursorLeft => {
+                if !self.pointer_lock.is_locked() {
+                    self.state.cursor = None;
+                }
+            }
+            GameInput::MouseButton { button, pressed } => {
+                let playing = self.state.phase == Phase::Playing;
+                if let MouseButton::Left | MouseButton::Right = button {
+                    if !pressed || playing {
+                        self.state.players[0].set_action(Action::Fire, pressed);
+                    }
+                }
+                if pressed && playing && self.state.pointer_lock && !self.pointer_lock.is_locked() {
+                    self.pointer_lock.request();
+                }
+            }
+            GameInput::Touch { id, phase, x, y } => match phase {
+                TouchPhase::Started => self.state.touch_started(id, x, y),
+                TouchPhase::Moved => self.state.touch_moved(id, x, y),
+                TouchPhase::Ended | TouchPhase::Cancelled => self.state.touch_ended(id),
+            },
+            GameInput::Gamepads(pads) => self.state.apply_gamepads(pads),
+            GameInput::GamepadConnection(event) => self.state.gamepad_event(event),
+        }
+    }
+
+    fn serialize(&self) -> String {
+        let state = &self.state;
+        let scores: Vec<String> = state
+            .players
+            .iter()
+            .map(|player| player.score.to_string())
+            .collect();
+        format!(
+            "stage={}\nscores={}\ncoins={}\n",
+            state.stage,
+            scores.join(","),
+            state.progress.currency
+        )
+    }
+
+    fn restart(&mut self, engine: &mut Engine) {
+        self.state.restart(engine.storage.as_mut());
+        set_cursor_style(&self.canvas, self.state.show_crosshair);
+        self.shop_panel.hide();
+        self.shop_visible = false;
+    }
+
+    fn configure(&mut self, engine: &mut Engine, setting: Setting) {
+        match setting {
+            Setting::Theme(theme) => self.state.set_theme(theme, engine.storage.as_mut()),
+            Setting::Config(config) => {
+                // Only options the page changed override the player's own toggles
+                let previous = self.state.config;
+                self.state.config = config;
+                if config.show_crosshair != previous.show_crosshair {
+                    self.set_crosshair(config.show_crosshair);
+                }
+                if config.pointer_lock != previous.pointer_lock {
+                    self.set_pointer_lock(config.pointer_lock);
+                }
+            }
+            Setting::Bindings(request) => self.state.rebind(request, engine.storage.as_mut()),
+            Setting::Touch(request) => self.state.configure_touch(request),
+        }
+    }
+
+    fn pause(&mut self) {
+        self.pointer_lock.release();
+    }
+
+    fn save(&mut self, engine: &mut Engine) {
+        // Coins are otherwise only saved when a stage or game ends
+        self.state.progress.save(engine.storage.as_mut());
+    }
+}
+
+// ### Utility Functions
+
+/// Hides the system cursor while the crosshair stands in for it.
+fn set_cursor_style(canvas: &web_sys::HtmlCanvasElement, crosshair: bool) {
+    let cursor = if crosshair { "none" } else { "crosshair" };
+    let _ = canvas.style().set_property("cursor", cursor);
+}
+
+/// Splits bullets in flight into three, as many as fit under `MULTIBALL_MAX_BULLETS`.
+fn split_bullets(bullets: &mut Vec<Bullet>) {
+    let room = MULTIBALL_MAX_BULLETS.saturating_sub(bullets.len()) / 2;
+    let mut split = Vec::with_capacity(room.min(bullets.len()) * 2);
+    for bullet in bullets.iter().take(room) {
+        split.push(bullet.split(MULTIBALL_ANGLE));
+        split.push(bullet.split(-MULTIBALL_ANGLE));
+    }
+    bullets.extend(split);
+}
+
+// ### Drawing Functions
+
+/// Queues one frame of the game in back-to-front order.
+fn draw_game(renderer: &mut dyn Renderer, game_state: &GameState, camera: &Camera) {
+    let theme = game_state.theme;
+    draw_playfield(renderer, theme, camera);
+
+    // Draw shield over the ground
+    if game_state.power_ups.is_active(PowerUpKind::Shield) {
+        renderer.rect(-1.0, GROUND_TOP, 1.0, GROUND_TOP + 0.01, theme.shield);
+    }
+
+    draw_bricks(renderer, &game_state.bricks, theme);
+
+    for player in &game_state.players {
+        // Draw cannon base with supports
+        let (x, y) = (player.cannon_x, player.cannon_y);
+        renderer.sprite(
+            Sprite::Base,
+            x - BASE_WIDTH / 2.0,
+            y - BASE_HEIGHT / 2.0,
+            x + BASE_WIDTH / 2.0,
+            y + BASE_HEIGHT / 2.0,
+            theme.base,
+        );
+        renderer.rect(
+            x - BASE_WIDTH / 2.0 - 0.01,
+            y - BASE_HEIGHT / 2.0,
+            x - BASE_WIDTH / 2.0,
+            y + BASE_HEIGHT / 2.0,
+            theme.support,
+        );
+        renderer.rect(
+            x + BASE_WIDTH / 2.0,
+            y - BASE_HEIGHT / 2.0,
+            x + BASE_WIDTH / 2.0 + 0.01,
+            y + BASE_HEIGHT / 2.0,
+            theme.support,
+        );
+
+        // Draw cannon barrel with a muzzle band in the player's colour
+        renderer.rotated_sprite(
+            Sprite::Barrel,
+            x,
+            y,
+            player.theta,
+            (-CANNON_WIDTH / 2.0, 0.0, CANNON_WIDTH / 2.0, CANNON_LENGTH),
+            theme.barrel,
+        );
+        renderer.rotated_rect(
+            x,
+            y,
+            player.theta,
+            (
+                -CANNON_WIDTH / 2.0,
+                CANNON_LENGTH * 0.8,
+                CANNON_WIDTH / 2.0,
+                CANNON_LENGTH,
+            ),
+            player.color,
+        );
+    }
+
+    // Draw bullets glowing
+    renderer.set_glow(1.0);
+    for bullet in &game_state.bullets {
+        let color = game_state.players[bullet.owner].color;
+        renderer.sprite(
+            Sprite::Bullet,
+            bullet.x - bullet.radius,
+            bullet.y - bullet.radius,
+            bullet.x + bullet.radius,
+            bullet.y + bullet.radius,
+            color,
+        );
+    }
+
+    // Draw pickups
+    renderer.set_glow(0.0);
+    for pickup in &game_state.pickups {
+        renderer.rect(
+            pickup.x - PICKUP_SIZE / 2.0,
+            pickup.y - PICKUP_SIZE / 2.0,
+            pickup.x + PICKUP_SIZE / 2.0,
+            pickup.y + PICKUP_SIZE / 2.0,
+            pickup.kind.color(),
+        );
+    }
+
+    // Draw particles glowing
+    renderer.set_glow(1.0);
+    game_state.particles.draw(renderer);
+    renderer.set_glow(0.0);
+
+    // Draw weapon meters on the ground, player two's on the right
+    for (index, player) in game_state.players.iter().enumerate() {
+        let meter_x = if index == 0 {
+            -0.95
+        } else {
+            0.95 - METER_WIDTH
+        };
+        if let Some(heat) = player.weapon.heat() {
+            let color = if heat.overheated {
+                [1.0, 0.1, 0.1, 1.0] // Red while locked
+            } else {
+                [1.0, 1.0 - heat.heat, 0.2, 1.0] // Yellow to red as it heats up
+            };
+            draw_meter(renderer, meter_x, -0.98, heat.heat, color);
+        }
+        if let Some(magazine) = player.weapon.magazine() {
+            let (fill, color) = if magazine.is_reloading() {
+                (magazine.reload_progress(), [0.6, 0.6, 0.6, 1.0])
+            } else {
+                (
+                    magazine.rounds as f32 / magazine.capacity as f32,
+                    AMMO_PICKUP_COLOR,
+                )
+            };
+            draw_meter(renderer, meter_x, -0.98 + METER_HEIGHT * 1.5, fill, color);
+        }
+    }
+
+    // Draw power-up timers in the top-right corner, one row per power-up
+    for (row, active) in game_state.power_ups.iter().enumerate() {
+        let x = 0.95 - METER_WIDTH;
+        let y = 0.95 - row as f32 * METER_HEIGHT * 2.0;
+        let color = active.kind.color();
+        draw_meter(renderer, x, y, active.fraction_remaining(), color);
+        // One pip per stack to the left of the timer
+        for stack in 0..active.stacks {
+            let pip_x = x - ((stack + 1) as f32) * METER_HEIGHT * 1.5;
+            renderer.rect(pip_x, y, pip_x + METER_HEIGHT, y + METER_HEIGHT, color);
+        }
+    }
+
+    if game_state.phase == Phase::Playing {
+        game_state
+            .touch
+            .draw(renderer, game_state.players.len(), theme.text);
+    }
+
+    // Draw the crosshair where the mouse aims
+    if let (true, Phase::Playing, Some((x, y))) = (
+        game_state.show_crosshair,
+        game_state.phase,
+        game_state.cursor,
+    ) {
+        draw_crosshair(renderer, x, y, theme.text);
+    }
+
+    draw_hud_text(renderer, game_state, camera);
+}
+
+/// Draws the score, stage, weapon status and current message.
+fn draw_hud_text(renderer: &mut dyn Renderer, game_state: &GameState, camera: &Camera) {
+    let (left, right, top) = hud_bounds(camera);
+
+    let theme = game_state.theme;
+    let style = TextStyle::new(HUD_TEXT_SIZE, theme.text);
+    text::draw_text(
+        renderer,
+        &game_state.hud_text(),
+        left + 0.05,
+        top - 0.05,
+        &style,
+    );
+
+    // Weapon status above each player's meters
+    let weapon_style = TextStyle::new(HUD_TEXT_SIZE * 0.7, theme.text);
+    for (index, player) in game_state.players.iter().enumerate() {
+        let mut label = player.weapon.name().to_string();
+        if player.weapon.heat().is_some_and(|heat| heat.overheated) {
+            label.push_str(" overheated");
+        } else if let Some(magazine) = player.weapon.magazine() {
+            if magazine.is_reloading() {
+                label.push_str(" reloading");
+            } else {
+                label.push_str(&format!(" {}/{}", magazine.rounds, magazine.reserve));
+            }
+        }
+        let label_top = -0.98 + METER_HEIGHT * 2.5 + weapon_style.size + 0.005;
+        if index == 0 {
+            text::draw_text(renderer, &label, left + 0.05, label_top, &weapon_style);
+        } else {
+            let style = weapon_style.aligned(Align::Right);
+            text::draw_text(renderer, &label, right - 0.05, label_top, &style);
+        }
+    }
+
+    if let Some((message, remaining)) = &game_state.message {
+        draw_message(renderer, message, *remaining, theme);
+    }
+}
+
+/// Draws a crosshair centred on (`x`, `y`) with a gap in the middle.
+fn draw_crosshair(renderer: &mut dyn Renderer, x: f32, y: f32, color: [f32; 4]) {
+    let (arm, gap, thickness) = (CROSSHAIR_SIZE, CROSSHAIR_SIZE / 3.0, CROSSHAIR_SIZE / 8.0);
+    renderer.rect(x - arm, y - thickness, x - gap, y + thickness, color);
+    renderer.rect(x + gap, y - thickness, x + arm, y + thickness, color);
+    renderer.rect(x - thickness, y - arm, x + thickness, y - gap, color);
+    renderer.rect(x - thickness, y + gap, x + thickness, y + arm, color);
+}
+
+/// Draws a horizontal HUD meter with its bottom-left corner at (`x`, `y`), filled to `fill` (0..1).
+fn draw_meter(renderer: &mut dyn Renderer, x: f32, y: f32, fill: f32, color: [f32; 4]) {
+    renderer.rect(
+        x,
+        y,
+        x + METER_WIDTH,
+        y + METER_HEIGHT,
+        [0.0, 0.0, 0.0, 0.5],
+    );
+    renderer.rect(
+        x,
+        y,
+        x + METER_WIDTH * fill.clamp(0.0, 1.0),
+        y + METER_HEIGHT,
+        color,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bullets(count: usize) -> Vec<Bullet> {
+        (0..count)
+            .map(|_| Bullet {
+                x: 0.0,
+                y: 0.0,
+                dx: 0.0,
+                dy: 1.0,
+                radius: 0.01,
+                health: 1,
+                damage: 1,
+                owner: 0,
+                pierce: 0,
+                last_hit: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn multiball_splits_every_bullet_into_three() {
+        let mut in_flight = bullets(2);
+        split_bullets(&mut in_flight);
+        assert_eq!(in_flight.len(), 6);
+        // The copies fan out either side of the original
+        assert!(in_flight[2].dx < 0.0 && in_flight[3].dx > 0.0);
+    }
+
+    #[test]
+    fn multiball_stops_at_the_bullet_cap() {
+        let mut in_flight = bullets(MULTIBALL_MAX_BULLETS - 5);
+        split_bullets(&mut in_flight);
+        // Room for five more bullets only lets two of them split
+        assert_eq!(in_flight.len(), MULTIBALL_MAX_BULLETS - 1);
+        split_bullets(&mut in_flight);
+        assert_eq!(in_flight.len(), MULTIBALL_MAX_BULLETS - 1);
+    }
+}
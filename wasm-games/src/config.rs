@@ -0,0 +1,514 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::camera::FitMode;
+use crate::playfield::{BRICK_COLUMNS, BRICK_ROWS, WORLD_HEIGHT, WORLD_WIDTH};
+
+// ### Game Configuration
+
+const BALL_SPEED: f32 = 1.0;
+const BRICK_WIDTH: f32 = 0.1;
+const BRICK_HEIGHT: f32 = 0.1;
+const BRICK_SPACING: f32 = 0.01;
+const ROTATION_SPEED: f32 = 2.0 * std::f32::consts::PI;
+const FRICTION: f32 = 0.9;
+const MAX_OFFSET: f32 = 0.02; // Maximum position offset for bricks
+const HIT_EFFECT_DURATION: f32 = 0.1; // Duration of hit flash in seconds
+const WEAPON_HEAT: bool = false; // Whether the cannon overheats; off keeps the endless stream
+const FINITE_AMMO: bool = false; // Whether weapons fire from magazines, so ammo can run out
+const AMMO_DROP_CHANCE: f32 = 0.25; // Chance a destroyed brick drops ammo
+const POWERUP_DROP_CHANCE: f32 = 0.1; // Chance a destroyed brick drops a power-up
+const SLOW_MOTION_FACTOR: f32 = 0.4; // World speed while slow-motion is active
+const CANNON_ACCELERATION: f32 = 4.0; // Driving acceleration in units per second squared
+const CANNON_BRAKING: f32 = 8.0; // Deceleration when not driving or reversing
+const CANNON_MAX_SPEED: f32 = 1.2;
+const BRICK_BASE_HEALTH: u32 = 5; // Brick health in the first stage, +1 per stage after
+const MAX_BRICK_HEALTH: u32 = 1000; // Highest first-stage health the page may set
+const FIT_MODE: FitMode = FitMode::Letterbox;
+const MAX_WORLD_SIZE: f32 = 10.0; // Largest world area the page may ask the camera to fit
+const MAX_RENDER_SCALE: f32 = 2.0; // Cap on the device pixel ratio, 0 for native
+const MIN_RENDER_SCALE: f32 = 0.25; // Range the page may set the cap to
+const MAX_RENDER_SCALE_CAP: f32 = 8.0;
+const SHOW_CROSSHAIR: bool = true; // Crosshair instead of the system cursor (C toggles)
+const POINTER_LOCK: bool = false; // Lock the pointer on click for relative aiming (L toggles)
+const BLOOM_STRENGTH: f32 = 1.5;
+const MAX_SHAKE: f32 = 0.03; // Camera offset at full trauma in world units
+const PARTICLE_SPEED: f32 = 1.0; // Scale on the speed of every particle emitter
+
+/// Tunables a host page can set when starting a game and change while it runs.
+///
+/// JavaScript passes them as a plain object with camelCase keys, and any key left out keeps its
+/// current value. Values are checked against sensible ranges, and changes can be made mid-game:
+/// the brick layout and health take effect from the next stage, weapon heat and ammunition from
+/// the next game, everything else from the next frame. The world size and fit mode only change
+/// how much around the playfield is shown, and the render scale how sharply it is drawn.
+/// Setting the crosshair or pointer lock overrides the player's own toggle until they press its
+/// key again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameConfig {
+    pub ball_speed: f32,     // Bullet speed in units per second, before weapons
+    pub rotation_speed: f32, // Barrel turn rate in radians per second
+    pub friction: f32,       // Fraction of bullet speed kept after each second
+    pub brick_width: f32,
+    pub brick_height: f32,
+    pub brick_spacing: f32,
+    pub brick_offset: f32,      // Largest random nudge of a brick off the grid
+    pub brick_base_health: u32, // Health in stage 1, +1 per stage after
+    pub hit_effect_duration: f32,
+    pub ammo_drop_chance: f32,
+    pub power_up_drop_chance: f32,
+    pub cannon_acceleration: f32,
+    pub cannon_braking: f32,
+    pub cannon_max_speed: f32,
+    pub slow_motion_factor: f32, // World speed while slow-motion is active
+    pub bloom_strength: f32,
+    pub max_shake: f32,      // Camera offset at full trauma in world units
+    pub particle_speed: f32, // Scale on every emitter's particle speeds
+    pub weapon_heat: bool,   // Firing for too long overheats the cannon
+    pub finite_ammo: bool,   // Magazines, and running dry ends the game
+    pub world_width: f32,    // Area the camera fits, around the 2 by 2 playfield
+    pub world_height: f32,
+    pub fit_mode: FitMode,
+    pub max_render_scale: f32, // Cap on pixels per CSS pixel, 0 for none
+    pub show_crosshair: bool,  // Crosshair instead of the system cursor
+    pub pointer_lock: bool,    // Clicking locks the pointer for relative aim
+}
+
+#[wasm_bindgen(typescript_custom_section)]
+const CONFIG_TYPE: &str = r#"
+export interface GameConfig {
+    ballSpeed?: number;
+    rotationSpeed?: number;
+    friction?: number;
+    brickWidth?: number;
+    brickHeight?: number;
+    brickSpacing?: number;
+    brickOffset?: number;
+    brickBaseHealth?: number;
+    hitEffectDuration?: number;
+    ammoDropChance?: number;
+    powerUpDropChance?: number;
+    cannonAcceleration?: number;
+    cannonBraking?: number;
+    cannonMaxSpeed?: number;
+    slowMotionFactor?: number;
+    bloomStrength?: number;
+    maxShake?: number;
+    particleSpeed?: number;
+    weaponHeat?: boolean;
+    finiteAmmo?: boolean;
+    worldWidth?: number;
+    worldHeight?: number;
+    fitMode?: "letterbox" | "expand" | "crop";
+    maxRenderScale?: number;
+    showCrosshair?: boolean;
+    pointerLock?: boolean;
+}
+"#;
+
+/// Names of the fields as JavaScript sees them.
+const FIELD_NAMES: [&str; 26] = [
+    "ballSpeed",
+    "rotationSpeed",
+    "friction",
+    "brickWidth",
+    "brickHeight",
+    "brickSpacing",
+    "brickOffset",
+    "brickBaseHealth",
+    "hitEffectDuration",
+    "ammoDropChance",
+    "powerUpDropChance",
+    "cannonAcceleration",
+    "cannonBraking",
+    "cannonMaxSpeed",
+    "slowMotionFactor",
+    "bloomStrength",
+    "maxShake",
+    "particleSpeed",
+    "weaponHeat",
+    "finiteAmmo",
+    "worldWidth",
+    "worldHeight",
+    "fitMode",
+    "maxRenderScale",
+    "showCrosshair",
+    "pointerLock",
+];
+
+/// A value from JavaScript, as read before it is checked against the field it is for.
+enum FieldValue {
+    Number(f64),
+    Flag(bool),
+    Text(String),
+    Other,
+}
+
+impl FieldValue {
+    fn from_js(value: &JsValue) -> Self {
+        if let Some(number) = value.as_f64() {
+            FieldValue::Number(number)
+        } else if let Some(flag) = value.as_bool() {
+            FieldValue::Flag(flag)
+        } else if let Some(text) = value.as_string() {
+            FieldValue::Text(text)
+        } else {
+            FieldValue::Other
+        }
+    }
+}
+
+enum FieldMut<'a> {
+    Float(&'a mut f32),
+    Count(&'a mut u32),
+    Flag(&'a mut bool),
+    Fit(&'a mut FitMode),
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            ball_speed: BALL_SPEED,
+            rotation_speed: ROTATION_SPEED,
+            friction: FRICTION,
+            brick_width: BRICK_WIDTH,
+            brick_height: BRICK_HEIGHT,
+            brick_spacing: BRICK_SPACING,
+            brick_offset: MAX_OFFSET,
+            brick_base_health: BRICK_BASE_HEALTH,
+            hit_effect_duration: HIT_EFFECT_DURATION,
+            ammo_drop_chance: AMMO_DROP_CHANCE,
+            power_up_drop_chance: POWERUP_DROP_CHANCE,
+            cannon_acceleration: CANNON_ACCELERATION,
+            cannon_braking: CANNON_BRAKING,
+            cannon_max_speed: CANNON_MAX_SPEED,
+            slow_motion_factor: SLOW_MOTION_FACTOR,
+            bloom_strength: BLOOM_STRENGTH,
+            max_shake: MAX_SHAKE,
+            particle_speed: PARTICLE_SPEED,
+            weapon_heat: WEAPON_HEAT,
+            finite_ammo: FINITE_AMMO,
+            world_width: WORLD_WIDTH,
+            world_height: WORLD_HEIGHT,
+            fit_mode: FIT_MODE,
+            max_render_scale: MAX_RENDER_SCALE,
+            show_crosshair: SHOW_CROSSHAIR,
+            pointer_lock: POINTER_LOCK,
+        }
+    }
+}
+
+impl GameConfig {
+    fn field_mut(&mut self, name: &str) -> Option<FieldMut<'_>> {
+        Some(match name {
+            "ballSpeed" => FieldMut::Float(&mut self.ball_speed),
+            "rotationSpeed" => FieldMut::Float(&mut self.rotation_speed),
+            "friction" => FieldMut::Float(&mut self.friction),
+            "brickWidth" => FieldMut::Float(&mut self.brick_width),
+            "brickHeight" => FieldMut::Float(&mut self.brick_height),
+            "brickSpacing" => FieldMut::Float(&mut self.brick_spacing),
+            "brickOffset" => FieldMut::Float(&mut self.brick_offset),
+            "brickBaseHealth" => FieldMut::Count(&mut self.brick_base_health),
+            "hitEffectDuration" => FieldMut::Float(&mut self.hit_effect_duration),
+            "ammoDropChance" => FieldMut::Float(&mut self.ammo_drop_chance),
+            "powerUpDropChance" => FieldMut::Float(&mut self.power_up_drop_chance),
+            "cannonAcceleration" => FieldMut::Float(&mut self.cannon_acceleration),
+            "cannonBraking" => FieldMut::Float(&mut self.cannon_braking),
+            "cannonMaxSpeed" => FieldMut::Float(&mut self.cannon_max_speed),
+            "slowMotionFactor" => FieldMut::Float(&mut self.slow_motion_factor),
+            "bloomStrength" => FieldMut::Float(&mut self.bloom_strength),
+            "maxShake" => FieldMut::Float(&mut self.max_shake),
+            "particleSpeed" => FieldMut::Float(&mut self.particle_speed),
+            "weaponHeat" => FieldMut::Flag(&mut self.weapon_heat),
+            "finiteAmmo" => FieldMut::Flag(&mut self.finite_ammo),
+            "worldWidth" => FieldMut::Float(&mut self.world_width),
+            "worldHeight" => FieldMut::Float(&mut self.world_height),
+            "fitMode" => FieldMut::Fit(&mut self.fit_mode),
+            "maxRenderScale" => FieldMut::Float(&mut self.max_render_scale),
+            "showCrosshair" => FieldMut::Flag(&mut self.show_crosshair),
+            "pointerLock" => FieldMut::Flag(&mut self.pointer_lock),
+            _ => return None,
+        })
+    }
+
+    /// This config with the fields set in `changes`, a JavaScript object, or an error naming
+    /// the first unknown or invalid field. `undefined` and `null` change nothing.
+    pub fn merged(&self, changes: &JsValue) -> Result<GameConfig, String> {
+        let mut config = *self;
+        if changes.is_undefined() || changes.is_null() {
+            return Ok(config);
+        }
+        if !changes.is_object() {
+            return Err("config must be an object".to_string());
+        }
+        let fields: Vec<_> = js_sys::Object::keys(changes.unchecked_ref())
+            .iter()
+            .map(|key| {
+                let value = js_sys::Reflect::get(changes, &key).unwrap_or(JsValue::UNDEFINED);
+                (
+                    key.as_string().unwrap_or_default(),
+                    FieldValue::from_js(&value),
+                )
+            })
+            .collect();
+        config.merge_fields(&fields)?;
+        Ok(config)
+    }
+
+    /// Sets each named field and checks the result, leaving fields not named as they were.
+    fn merge_fields(&mut self, fields: &[(String, FieldValue)]) -> Result<(), String> {
+        let mut config = *self;
+        for (name, value) in fields {
+            let name = name.as_str();
+            match config.field_mut(name) {
+                Some(FieldMut::Float(field)) => {
+                    // Numbers beyond f32's range would become infinite
+                    let number = number(value, name)? as f32;
+                    if !number.is_finite() {
+                        return Err(format!("{} is out of range", name));
+                    }
+                    *field = number;
+                }
+                Some(FieldMut::Count(field)) => {
+                    let number = number(value, name)?;
+                    if number < 0.0 || number.fract() != 0.0 {
+                        return Err(format!("{} must be a whole number", name));
+                    }
+                    if number > u32::MAX as f64 {
+                        return Err(format!("{} is out of range", name));
+                    }
+                    *field = number as u32;
+                }
+                Some(FieldMut::Flag(field)) => match value {
+                    FieldValue::Flag(flag) => *field = *flag,
+                    _ => return Err(format!("{} must be true or false", name)),
+                },
+                Some(FieldMut::Fit(field)) => {
+                    *field = match value {
+                        FieldValue::Text(fit) => FitMode::by_name(fit),
+                        _ => None,
+                    }
+                    .ok_or_else(|| format!("{} must be letterbox, expand or crop", name))?;
+                }
+                None => return Err(format!("Unknown config field: {}", name)),
+            }
+        }
+        config.validate()?;
+        *self = config;
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        // Lowest and highest values that still play. Tiny bricks or huge speeds would swamp
+        // the frame with bricks or tunnel bullets through them.
+        let ranges = [
+            ("ballSpeed", self.ball_speed, 0.05, 10.0),
+            ("rotationSpeed", self.rotation_speed, 0.1, 50.0),
+            ("brickWidth", self.brick_width, 0.02, 2.0),
+            ("brickHeight", self.brick_height, 0.02, 1.5),
+            ("brickSpacing", self.brick_spacing, 0.0, 0.5),
+            ("brickOffset", self.brick_offset, 0.0, 0.5),
+            ("hitEffectDuration", self.hit_effect_duration, 0.0, 5.0),
+            ("cannonAcceleration", self.cannon_acceleration, 0.1, 100.0),
+            ("cannonBraking", self.cannon_braking, 0.1, 100.0),
+            ("cannonMaxSpeed", self.cannon_max_speed, 0.05, 10.0),
+            ("bloomStrength", self.bloom_strength, 0.0, 10.0),
+            ("maxShake", self.max_shake, 0.0, 0.5),
+            ("particleSpeed", self.particle_speed, 0.0, 5.0),
+        ];
+        for &(name, value, min, max) in ranges.iter() {
+            if !(min..=max).contains(&value) {
+                return Err(format!("{} must be from {} to {}", name, min, max));
+            }
+        }
+        let fractions = [
+            ("friction", self.friction),
+            ("slowMotionFactor", self.slow_motion_factor),
+        ];
+        for &(name, value) in fractions.iter() {
+            if value <= 0.0 || value > 1.0 {
+                return Err(format!("{} must be above 0 and at most 1", name));
+            }
+        }
+        if self.ammo_drop_chance < 0.0
+            || self.power_up_drop_chance < 0.0
+            || self.ammo_drop_chance + self.power_up_drop_chance > 1.0
+        {
+            return Err("drop chances must be between 0 and 1 in total".to_string());
+        }
+        if !(1..=MAX_BRICK_HEALTH).contains(&self.brick_base_health) {
+            return Err(format!(
+                "brickBaseHealth must be from 1 to {}",
+                MAX_BRICK_HEALTH
+            ));
+        }
+        // The grid of bricks must fit across the playfield and leave the bottom quarter free
+        let pitch_x = self.brick_width + self.brick_spacing;
+        let pitch_y = self.brick_height + self.brick_spacing;
+        if BRICK_COLUMNS as f32 * pitch_x > WORLD_WIDTH
+            || BRICK_ROWS as f32 * pitch_y > WORLD_HEIGHT * 0.75
+        {
+            return Err("bricks are too large to fit the playfield".to_string());
+        }
+        if self.brick_offset * 2.0 > self.brick_width.min(self.brick_height) {
+            return Err("brickOffset must be at most half the brick size".to_string());
+        }
+        // The camera must show at least the whole playfield
+        let world = [
+            ("worldWidth", self.world_width, WORLD_WIDTH),
+            ("worldHeight", self.world_height, WORLD_HEIGHT),
+        ];
+        for &(name, value, playfield) in world.iter() {
+            if !(playfield..=MAX_WORLD_SIZE).contains(&value) {
+                return Err(format!(
+                    "{} must be from {} to {}",
+                    name, playfield, MAX_WORLD_SIZE
+                ));
+            }
+        }
+        if self.max_render_scale != 0.0
+            && !(MIN_RENDER_SCALE..=MAX_RENDER_SCALE_CAP).contains(&self.max_render_scale)
+        {
+            return Err(format!(
+                "maxRenderScale must be 0 or from {} to {}",
+                MIN_RENDER_SCALE, MAX_RENDER_SCALE_CAP
+            ));
+        }
+        Ok(())
+    }
+
+    /// The cap on backing-store pixels per CSS pixel, if any.
+    pub fn render_scale_cap(&self) -> Option<f64> {
+        if self.max_render_scale > 0.0 {
+            Some(self.max_render_scale as f64)
+        } else {
+            None
+        }
+    }
+
+    /// A plain JavaScript object with every field, as accepted by `merged`.
+    pub fn to_js(self) -> JsValue {
+        let object = js_sys::Object::new();
+        let mut config = self;
+        for &name in FIELD_NAMES.iter() {
+            let value = match config.field_mut(name) {
+                Some(FieldMut::Float(field)) => JsValue::from(*field),
+                Some(FieldMut::Count(field)) => JsValue::from(*field),
+                Some(FieldMut::Flag(field)) => JsValue::from(*field),
+                Some(FieldMut::Fit(field)) => JsValue::from_str(field.name()),
+                None => continue,
+            };
+            // Setting a property on a fresh plain object can't fail
+            let _ = js_sys::Reflect::set(&object, &name.into(), &value);
+        }
+        object.into()
+    }
+}
+
+/// `value` as a finite number, or an error naming the field it was given for.
+fn number(value: &FieldValue, name: &str) -> Result<f64, String> {
+    match value {
+        FieldValue::Number(number) if number.is_finite() => Ok(*number),
+        _ => Err(format!("{} must be a number", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(GameConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn values_out_of_range_are_rejected() {
+        let changes: [fn(&mut GameConfig); 6] = [
+            |config| config.ball_speed = 0.0,
+            |config| config.ball_speed = 1e30,
+            |config| config.brick_width = 0.001,
+            |config| config.brick_base_health = 0,
+            |config| config.brick_base_health = u32::MAX,
+            |config| config.world_width = 1.0,
+        ];
+        for change in changes.iter() {
+            let mut config = GameConfig::default();
+            change(&mut config);
+            assert!(config.validate().is_err(), "{:?} was accepted", config);
+        }
+    }
+
+    fn merge(fields: Vec<(&str, FieldValue)>) -> Result<GameConfig, String> {
+        let fields: Vec<_> = fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        let mut config = GameConfig::default();
+        config.merge_fields(&fields).map(|()| config)
+    }
+
+    #[test]
+    fn every_field_rejects_bad_numbers() {
+        for &name in FIELD_NAMES.iter() {
+            for &number in [f64::NAN, f64::INFINITY, -1.0, 1e300, u32::MAX as f64 + 1.0].iter() {
+                let result = merge(vec![(name, FieldValue::Number(number))]);
+                assert!(result.is_err(), "{} accepted {}", name, number);
+            }
+        }
+    }
+
+    #[test]
+    fn every_field_accepts_its_default() {
+        let mut defaults = GameConfig::default();
+        for &name in FIELD_NAMES.iter() {
+            let value = match defaults.field_mut(name) {
+                Some(FieldMut::Float(field)) => FieldValue::Number(*field as f64),
+                Some(FieldMut::Count(field)) => FieldValue::Number(*field as f64),
+                Some(FieldMut::Flag(field)) => FieldValue::Flag(*field),
+                Some(FieldMut::Fit(field)) => FieldValue::Text(field.name().to_string()),
+                None => panic!("{} has no field", name),
+            };
+            assert_eq!(merge(vec![(name, value)]), Ok(GameConfig::default()));
+        }
+    }
+
+    #[test]
+    fn missing_fields_keep_their_values() {
+        assert_eq!(merge(Vec::new()), Ok(GameConfig::default()));
+
+        let config = merge(vec![
+            ("ballSpeed", FieldValue::Number(2.0)),
+            ("weaponHeat", FieldValue::Flag(true)),
+            ("fitMode", FieldValue::Text("crop".to_string())),
+        ])
+        .unwrap();
+        let expected = GameConfig {
+            ball_speed: 2.0,
+            weapon_heat: true,
+            fit_mode: FitMode::Crop,
+            ..GameConfig::default()
+        };
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn a_bad_field_changes_nothing() {
+        let mut config = GameConfig::default();
+        let fields = vec![
+            ("ballSpeed".to_string(), FieldValue::Number(2.0)),
+            (
+                "fitMode".to_string(),
+                FieldValue::Text("stretch".to_string()),
+            ),
+        ];
+        assert!(config.merge_fields(&fields).is_err());
+        assert_eq!(config, GameConfig::default());
+
+        let fields = vec![("brickSize".to_string(), FieldValue::Number(0.1))];
+        assert_eq!(
+            config.merge_fields(&fields),
+            Err("Unknown config field: brickSize".to_string())
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlCanvasElement, ResizeObserver};
+use winit::dpi::PhysicalPosition;
+
+// ### Display
+
+/// Keeps the canvas backing store at its CSS size times the device pixel ratio, so the game is
+/// sharp on high-DPI screens.
+///
+/// The ratio can be capped to trade sharpness for fill rate. The canvas fills its container,
+/// whose size changes are picked up by a `ResizeObserver` (or a window `resize` listener where
+/// that's unsupported) and applied by `take_resize` from the game loop.
+///
+/// It also tracks the canvas's WebGL context being lost and restored, which happens e.g. when
+/// a mobile browser reclaims the GPU from a background tab.
+pub struct Display {
+    canvas: HtmlCanvasElement,
+    max_render_scale: Option<f64>, // Cap on backing-store pixels per CSS pixel
+    resized: Rc<Cell<bool>>,
+    on_resize: Closure<dyn FnMut()>,
+    resize_observer: Option<ResizeObserver>, // None when listening to the window instead
+    context_lost: Rc<Cell<bool>>,
+    context_restored: Rc<Cell<bool>>, // Restored since the renderer was last rebuilt
+    on_context_lost: Closure<dyn FnMut(web_sys::Event)>,
+    on_context_restored: Closure<dyn FnMut()>,
+}
+
+impl Display {
+    pub fn new(
+        canvas: HtmlCanvasElement,
+        container: &Element,
+        max_render_scale: Option<f64>,
+    ) -> Self {
+        let resized = Rc::new(Cell::new(true));
+        let flag = resized.clone();
+        let on_resize = Closure::wrap(Box::new(move || flag.set(true)) as Box<dyn FnMut()>);
+        let resize_observer = match ResizeObserver::new(on_resize.as_ref().unchecked_ref()) {
+            Ok(observer) => {
+                observer.observe(container);
+                Some(observer)
+            }
+            Err(_) => {
+                web_sys::window()
+                    .unwrap()
+                    .add_event_listener_with_callback("resize", on_resize.as_ref().unchecked_ref())
+                    .unwrap();
+                None
+            }
+        };
+
+        let context_lost = Rc::new(Cell::new(false));
+        let context_restored = Rc::new(Cell::new(false));
+        let lost = context_lost.clone();
+        let on_context_lost = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            // Without this the browser never restores the context
+            event.prevent_default();
+            log::warn!("WebGL context lost, waiting for it to be restored");
+            lost.set(true);
+        }) as Box<dyn FnMut(web_sys::Event)>);
+        let (lost, restored) = (context_lost.clone(), context_restored.clone());
+        let on_context_restored = Closure::wrap(Box::new(move || {
+            lost.set(false);
+            restored.set(true);
+        }) as Box<dyn FnMut()>);
+        let _ = canvas.add_event_listener_with_callback(
+            "webglcontextlost",
+            on_context_lost.as_ref().unchecked_ref(),
+        );
+        let _ = canvas.add_event_listener_with_callback(
+            "webglcontextrestored",
+            on_context_restored.as_ref().unchecked_ref(),
+        );
+
+        Display {
+            canvas,
+            max_render_scale,
+            resized,
+            on_resize,
+            resize_observer,
+            context_lost,
+            context_restored,
+            on_context_lost,
+            on_context_restored,
+        }
+    }
+
+    /// Whether the WebGL context is lost, in which case nothing should be drawn.
+    pub fn is_context_lost(&self) -> bool {
+        self.context_lost.get()
+    }
+
+    /// Whether the context was restored since the last call, so the renderer must rebuild its
+    /// GL objects.
+    pub fn take_context_restored(&self) -> bool {
+        self.context_restored.replace(false)
+    }
+
+    /// Changes the cap on the device pixel ratio, refitting the backing store on the next frame.
+    pub fn set_max_render_scale(&mut self, max_render_scale: Option<f64>) {
+        if max_render_scale != self.max_render_scale {
+            self.max_render_scale = max_render_scale;
+            self.mark_resized();
+        }
+    }
+
+    /// Backing-store pixels per CSS pixel.
+    pub fn render_scale(&self) -> f64 {
+        let ratio = web_sys::window().unwrap().device_pixel_ratio();
+        match self.max_render_scale {
+            Some(max) => ratio.min(max),
+            None => ratio,
+        }
+    }
+
+    /// Sizes the backing store to the canvas's CSS size, returning the new size in pixels.
+    pub fn fit_canvas(&self) -> (u32, u32) {
+        let scale = self.render_scale();
+        let width = (self.canvas.client_width() as f64 * scale).round() as u32;
+        let height = (self.canvas.client_height() as f64 * scale).round() as u32;
+        self.canvas.set_width(width);
+        self.canvas.set_height(height);
+        (width, height)
+    }
+
+    /// Flags the backing store for refitting, e.g. after winit resized the canvas itself.
+    pub fn mark_resized(&self) {
+        self.resized.set(true);
+    }
+
+    /// Refits the backing store if the container was resized since the last call.
+    pub fn take_resize(&self) -> Option<(u32, u32)> {
+        if self.resized.replace(false) {
+            Some(self.fit_canvas())
+        } else {
+            None
+        }
+    }
+
+    /// Converts a position in CSS pixels into backing-store pixels.
+    pub fn css_to_canvas_pixels(&self, x: f64, y: f64) -> (f32, f32) {
+        let scale = self.render_scale();
+        ((x * scale) as f32, (y * scale) as f32)
+    }
+
+    /// Converts a position from winit, which scales CSS pixels by the uncapped device pixel
+    /// ratio, into backing-store pixels.
+    pub fn to_canvas_pixels(&self, position: PhysicalPosition<f64>) -> (f32, f32) {
+        let ratio = web_sys::window().unwrap().device_pixel_ratio();
+        let factor = self.render_scale() / ratio;
+        ((position.x * factor) as f32, (position.y * factor) as f32)
+    }
+}
+
+impl Drop for Display {
+    fn drop(&mut self) {
+        match &self.resize_observer {
+            Some(observer) => observer.disconnect(),
+            None => {
+                if let Some(window) = web_sys::window() {
+                    let _ = window.remove_event_listener_with_callback(
+                        "resize",
+                        self.on_resize.as_ref().unchecked_ref(),
+                    );
+                }
+            }
+        }
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "webglcontextlost",
+            self.on_context_lost.as_ref().unchecked_ref(),
+        );
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "webglcontextrestored",
+            self.on_context_restored.as_ref().unchecked_ref(),
+        );
+    }
+}
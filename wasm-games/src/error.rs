@@ -0,0 +1,55 @@
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+// ### Initialization Errors
+
+/// Why the game couldn't start.
+///
+/// JavaScript receives it as an `Error` with a `code` property of "unknown-game", "config",
+/// "dom", "webgl2-unavailable" or "renderer", so the page can show a fallback message.
+#[derive(Debug)]
+pub enum InitError {
+    /// No game is registered under the id passed to `initialize`.
+    UnknownGame(String),
+    /// The config passed to `initialize` has an unknown or invalid field.
+    Config(String),
+    /// The page's window, document or container couldn't be used.
+    Dom(String),
+    /// The browser or device doesn't support WebGL2.
+    WebGl2Unavailable,
+    /// Shaders or GPU buffers couldn't be created.
+    Renderer(String),
+}
+
+impl InitError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            InitError::UnknownGame(_) => "unknown-game",
+            InitError::Config(_) => "config",
+            InitError::Dom(_) => "dom",
+            InitError::WebGl2Unavailable => "webgl2-unavailable",
+            InitError::Renderer(_) => "renderer",
+        }
+    }
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InitError::UnknownGame(id) => write!(f, "Unknown game: {}", id),
+            InitError::Config(message) => write!(f, "Invalid config: {}", message),
+            InitError::Dom(message) => write!(f, "Cannot set up the page: {}", message),
+            InitError::WebGl2Unavailable => write!(f, "WebGL2 is not available"),
+            InitError::Renderer(message) => write!(f, "Cannot start the renderer: {}", message),
+        }
+    }
+}
+
+impl From<InitError> for JsValue {
+    fn from(error: InitError) -> Self {
+        let js_error = js_sys::Error::new(&error.to_string());
+        // Setting a property on a fresh Error object can't fail
+        let _ = js_sys::Reflect::set(&js_error, &"code".into(), &error.code().into());
+        js_error.into()
+    }
+}
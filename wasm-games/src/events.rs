@@ -0,0 +1,125 @@
+use wasm_bindgen::prelude::*;
+use web_sys::{CustomEvent, CustomEventInit, Element};
+
+// ### Game Events
+
+/// Something the host page may want to know about, e.g. to draw its own HUD.
+pub enum GameEvent {
+    ScoreChanged { player: usize, score: u32 },
+    BrickDestroyed { player: usize, x: f32, y: f32 }, // Brick centre in world units
+    StageCleared { stage: u32 },
+    GameOver { stage: u32, scores: Vec<u32> },
+    WeaponChanged { player: usize, weapon: &'static str },
+}
+
+pub const EVENT_NAMES: [&str; 5] = [
+    "score-changed",
+    "brick-destroyed",
+    "stage-cleared",
+    "game-over",
+    "weapon-changed",
+];
+
+#[wasm_bindgen(typescript_custom_section)]
+const EVENT_TYPES: &str = r#"
+export interface ScoreChangedDetail { player: number; score: number; }
+export interface BrickDestroyedDetail { player: number; x: number; y: number; }
+export interface StageClearedDetail { stage: number; }
+export interface GameOverDetail { stage: number; scores: number[]; }
+export interface WeaponChangedDetail { player: number; weapon: string; }
+export interface GameEventMap {
+    "score-changed": ScoreChangedDetail;
+    "brick-destroyed": BrickDestroyedDetail;
+    "stage-cleared": StageClearedDetail;
+    "game-over": GameOverDetail;
+    "weapon-changed": WeaponChangedDetail;
+}
+"#;
+
+impl GameEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameEvent::ScoreChanged { .. } => EVENT_NAMES[0],
+            GameEvent::BrickDestroyed { .. } => EVENT_NAMES[1],
+            GameEvent::StageCleared { .. } => EVENT_NAMES[2],
+            GameEvent::GameOver { .. } => EVENT_NAMES[3],
+            GameEvent::WeaponChanged { .. } => EVENT_NAMES[4],
+        }
+    }
+
+    /// The payload as a plain object, shaped like the matching `GameEventMap` entry.
+    pub fn detail(&self) -> JsValue {
+        let detail = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            // Setting a property on a fresh plain object can't fail
+            let _ = js_sys::Reflect::set(&detail, &key.into(), &value);
+        };
+        match self {
+            GameEvent::ScoreChanged { player, score } => {
+                set("player", (*player as u32).into());
+                set("score", (*score).into());
+            }
+            GameEvent::BrickDestroyed { player, x, y } => {
+                set("player", (*player as u32).into());
+                set("x", (*x).into());
+                set("y", (*y).into());
+            }
+            GameEvent::StageCleared { stage } => set("stage", (*stage).into()),
+            GameEvent::GameOver { stage, scores } => {
+                set("stage", (*stage).into());
+                let scores: js_sys::Array =
+                    scores.iter().map(|&score| JsValue::from(score)).collect();
+                set("scores", scores.into());
+            }
+            GameEvent::WeaponChanged { player, weapon } => {
+                set("player", (*player as u32).into());
+                set("weapon", (*weapon).into());
+            }
+        }
+        detail.into()
+    }
+}
+
+/// Delivers game events to callbacks registered through the game's handle, and as bubbling
+/// `CustomEvent`s with the payload in `detail` on the game's container.
+pub struct EventDispatcher {
+    target: Element,
+    callbacks: Vec<(&'static str, js_sys::Function)>,
+}
+
+impl EventDispatcher {
+    pub fn new(target: Element) -> Self {
+        EventDispatcher {
+            target,
+            callbacks: Vec::new(),
+        }
+    }
+
+    pub fn subscribe(&mut self, name: &'static str, callback: js_sys::Function) {
+        self.callbacks.push((name, callback));
+    }
+
+    pub fn dispatch(&self, event: &GameEvent) {
+        let name = event.name();
+        let detail = event.detail();
+        for (_, callback) in self
+            .callbacks
+            .iter()
+            .filter(|(subscribed, _)| *subscribed == name)
+        {
+            if let Err(error) = callback.call1(&JsValue::NULL, &detail) {
+                log::warn!("The {} callback threw: {:?}", name, error);
+            }
+        }
+
+        let init = CustomEventInit::new();
+        init.set_bubbles(true);
+        init.set_detail(&detail);
+        match CustomEvent::new_with_event_init_dict(name, &init) {
+            Ok(custom_event) => {
+                let _ = self.target.dispatch_event(&custom_event);
+            }
+            Err(error) => log::warn!("Cannot create the {} event: {:?}", name, error),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use web_sys::{Element, HtmlCanvasElement};
+use winit::event::{MouseButton, TouchPhase, VirtualKeyCode};
+
+use crate::audio::Audio;
+use crate::breakout::Breakout;
+use crate::camera::Camera;
+use crate::cannon::{CannonGame, GameMode};
+use crate::config::GameConfig;
+use crate::display::Display;
+use crate::events::GameEvent;
+use crate::gamepad::{PadEvent, PadInput};
+use crate::input::Action;
+use crate::renderer::Renderer;
+use crate::storage::Storage;
+use crate::theme::Theme;
+use crate::touch::TouchScheme;
+
+// ### Games
+
+/// Services the engine runs for whichever game is loaded.
+///
+/// The engine owns the loop, the renderer and the page's input; everything a game reaches for
+/// while it updates is here.
+pub struct Engine {
+    pub camera: Camera, // Resized to the canvas and used to unproject input
+    pub display: Display,
+    pub storage: Box<dyn Storage>,
+    pub audio: Audio,
+    pub events: Vec<GameEvent>, // For the page, dispatched after every update
+}
+
+impl Engine {
+    /// World units per CSS pixel of mouse movement.
+    pub fn world_units_per_css_pixel(&self) -> f32 {
+        self.display.render_scale() as f32 / self.camera.scale()
+    }
+}
+
+/// Input from the page, with positions already in world units.
+pub enum GameInput<'a> {
+    Key {
+        key: VirtualKeyCode,
+        pressed: bool,
+    },
+    CursorMoved {
+        x: f32,
+        y: f32,
+    },
+    CursorLeft,
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+    },
+    Gamepads(&'a [PadInput]), // Every frame, one entry per connected pad
+    GamepadConnection(PadEvent),
+}
+
+impl GameInput<'_> {
+    /// Whether this lets go of something. Games still get these while paused, so nothing stays
+    /// held after resuming.
+    pub fn is_release(&self) -> bool {
+        match self {
+            GameInput::Key { pressed, .. } | GameInput::MouseButton { pressed, .. } => !pressed,
+            GameInput::Touch { phase, .. } => {
+                *phase == TouchPhase::Ended || *phase == TouchPhase::Cancelled
+            }
+            GameInput::CursorLeft => true,
+            _ => false,
+        }
+    }
+}
+
+/// A setting changed from JavaScript. Games ignore the ones they have no use for.
+pub enum Setting {
+    Theme(&'static Theme),
+    Config(GameConfig),
+    Bindings(BindingRequest),
+    Touch(TouchRequest),
+}
+
+/// A change to the controls asked for from JavaScript.
+pub enum BindingRequest {
+    Bind(usize, Action, String),
+    Clear(usize, Action),
+    Reset,
+}
+
+/// A change to the touch controls asked for from JavaScript.
+pub enum TouchRequest {
+    Scheme(TouchScheme),
+    DeadZones { joystick: f32, drag: f32 },
+}
+
+/// One game the engine can run.
+pub trait Game {
+    /// Advances the game by `delta_time` seconds of real time.
+    fn update(&mut self, engine: &mut Engine, delta_time: f32);
+
+    /// Sets up the camera, effects and background, then draws the frame. The engine presents
+    /// it afterwards.
+    fn render(&self, renderer: &mut dyn Renderer, camera: &mut Camera);
+
+    fn handle_input(&mut self, engine: &mut Engine, input: GameInput);
+
+    /// A snapshot of the game's progress as "name=value" lines, for the page to show or keep.
+    fn serialize(&self) -> String;
+
+    /// Starts over from the beginning, keeping what the player saved.
+    fn restart(&mut self, engine: &mut Engine);
+
+    fn configure(&mut self, _engine: &mut Engine, _setting: Setting) {}
+
+    /// Called when the page pauses the game, e.g. to hand back the pointer.
+    fn pause(&mut self) {}
+
+    /// Called before the game is torn down, to save anything it would otherwise lose.
+    fn save(&mut self, _engine: &mut Engine) {}
+}
+
+/// What a game gets to set itself up with.
+pub struct GameSetup<'a> {
+    pub container: &'a Element,
+    pub canvas: &'a HtmlCanvasElement,
+    pub config: GameConfig,
+    pub storage: &'a dyn Storage,
+}
+
+/// An entry in the registry `initialize` picks games from.
+pub struct GameInfo {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub players: usize, // Players with their own controls
+    pub create: fn(GameSetup) -> Box<dyn Game>,
+}
+
+pub static GAMES: [GameInfo; 3] = [
+    GameInfo {
+        id: "cannon",
+        title: "Brick Breaker with Cannon",
+        players: 1,
+        create: |setup| Box::new(CannonGame::new(GameMode::Single, setup)),
+    },
+    GameInfo {
+        id: "cannon-coop",
+        title: "Brick Breaker with Cannon: Co-op",
+        players: 2,
+        create: |setup| Box::new(CannonGame::new(GameMode::CoOp, setup)),
+    },
+    GameInfo {
+        id: "breakout",
+        title: "Breakout",
+        players: 1,
+        create: |setup| Box::new(Breakout::new(setup.config, setup.storage)),
+    },
+];
+
+pub fn by_id(id: &str) -> Option<&'static GameInfo> {
+    GAMES.iter().find(|game| game.id == id)
+}
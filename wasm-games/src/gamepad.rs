@@ -0,0 +1,245 @@
+use std::cell::Cell;
+use wasm_bindgen::JsCast;
+use web_sys::{Gamepad, GamepadButton};
+
+// ### Gamepads
+
+const GAMEPAD_DEAD_ZONE: f32 = 0.15;
+const GAMEPAD_RESPONSE_EXPONENT: f32 = 2.0; // Stick response curve; above 1 favours fine aim
+const GAMEPAD_TRIGGER_THRESHOLD: f32 = 0.3; // Trigger travel that counts as firing
+
+// Standard mapping indices
+const LEFT_STICK_X: u32 = 0;
+const RIGHT_STICK_X: u32 = 2;
+const LEFT_SHOULDER: u32 = 4;
+const RIGHT_SHOULDER: u32 = 5;
+const LEFT_TRIGGER: u32 = 6;
+const RIGHT_TRIGGER: u32 = 7;
+
+thread_local! {
+    // Every game on the page sees the same pads, so only one instance at a time reads them
+    static ACTIVE_INSTANCE: Cell<u32> = const { Cell::new(0) };
+    static NEXT_INSTANCE: Cell<u32> = const { Cell::new(0) };
+}
+
+/// What one gamepad asked for since the last poll, in the same terms as the keyboard.
+#[derive(Default)]
+pub struct PadInput {
+    pub drive: f32,         // Left stick, -1.0 to 1.0 after the dead zone and curve
+    pub aim: f32,           // Right stick, likewise
+    pub fire: Option<bool>, // A trigger was pulled (true) or both released (false)
+    pub switch_weapon: Option<bool>, // A shoulder was pressed: right (true) or left (false)
+}
+
+pub enum PadEvent {
+    Connected(String),
+    Disconnected(String),
+}
+
+/// Button state of a connected pad from the previous poll, for edge detection.
+struct PadMemory {
+    index: u32,
+    id: String,
+    firing: bool,
+    shoulders: (bool, bool),
+}
+
+/// Polls the browser Gamepad API once per frame.
+///
+/// Pads are handed out to players in the order of their slots, and plugging one in or out is
+/// detected by comparing the connected pads with the previous poll. With several games on one
+/// page, only the one created, focused or clicked most recently gets the pads; the others read
+/// nothing until they claim them back.
+pub struct Gamepads {
+    instance: u32,
+    active: bool, // Whether the last poll read the pads
+    known: Vec<PadMemory>,
+}
+
+impl Gamepads {
+    /// Pads for a new game, which takes them over from any other game on the page.
+    pub fn new() -> Self {
+        let instance = NEXT_INSTANCE.with(|next| {
+            let instance = next.get();
+            next.set(instance + 1);
+            instance
+        });
+        let gamepads = Gamepads {
+            instance,
+            active: false,
+            known: Vec::new(),
+        };
+        gamepads.claim();
+        gamepads
+    }
+
+    /// Routes the pads to this game from now on, as when the player interacts with it.
+    pub fn claim(&self) {
+        ACTIVE_INSTANCE.with(|active| active.set(self.instance));
+    }
+
+    fn is_active(&self) -> bool {
+        ACTIVE_INSTANCE.with(|active| active.get() == self.instance)
+    }
+
+    /// Reads every connected pad and reports pads plugged in or out since the last poll. Once
+    /// another game has claimed the pads, this releases anything held and then reads nothing.
+    pub fn poll(&mut self) -> (Vec<PadInput>, Vec<PadEvent>) {
+        if !self.is_active() {
+            return (self.release(), Vec::new());
+        }
+        self.active = true;
+
+        let pads: Vec<Gamepad> = web_sys::window()
+            .and_then(|window| window.navigator().get_gamepads().ok())
+            .map(|gamepads| {
+                gamepads
+                    .iter()
+                    .filter_map(|gamepad| gamepad.dyn_into::<Gamepad>().ok())
+                    .filter(|gamepad| gamepad.connected())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut events = Vec::new();
+        self.known.retain(|memory| {
+            let present = pads.iter().any(|pad| pad.index() == memory.index);
+            if !present {
+                events.push(PadEvent::Disconnected(memory.id.clone()));
+            }
+            present
+        });
+
+        let mut inputs = Vec::with_capacity(pads.len());
+        for pad in &pads {
+            let position = match self.known.iter().position(|m| m.index == pad.index()) {
+                Some(position) => position,
+                None => {
+                    events.push(PadEvent::Connected(pad.id()));
+                    self.known.push(PadMemory {
+                        index: pad.index(),
+                        id: pad.id(),
+                        firing: false,
+                        shoulders: (false, false),
+                    });
+                    self.known.len() - 1
+                }
+            };
+            let memory = &mut self.known[position];
+
+            let axis = |index: u32| pad.axes().get(index).as_f64().unwrap_or(0.0) as f32;
+            let button = |index: u32| {
+                pad.buttons()
+                    .get(index)
+                    .dyn_into::<GamepadButton>()
+                    .ok()
+                    .map_or(0.0, |button| {
+                        if button.pressed() {
+                            button.value().max(1.0) as f32
+                        } else {
+                            button.value() as f32
+                        }
+                    })
+            };
+            let mut input = PadInput {
+                drive: shape_axis(axis(LEFT_STICK_X)),
+                aim: shape_axis(axis(RIGHT_STICK_X)),
+                ..PadInput::default()
+            };
+
+            let firing =
+                button(LEFT_TRIGGER).max(button(RIGHT_TRIGGER)) > GAMEPAD_TRIGGER_THRESHOLD;
+            if firing != memory.firing {
+                input.fire = Some(firing);
+                memory.firing = firing;
+            }
+
+            let shoulders = (button(LEFT_SHOULDER) > 0.5, button(RIGHT_SHOULDER) > 0.5);
+            if shoulders.1 && !memory.shoulders.1 {
+                input.switch_weapon = Some(true);
+            } else if shoulders.0 && !memory.shoulders.0 {
+                input.switch_weapon = Some(false);
+            }
+            memory.shoulders = shoulders;
+
+            inputs.push(input);
+        }
+        (inputs, events)
+    }
+
+    /// Neutral input for every pad, letting go of held triggers, on the first poll after the
+    /// pads were claimed by another game.
+    fn release(&mut self) -> Vec<PadInput> {
+        if !std::mem::take(&mut self.active) {
+            return Vec::new();
+        }
+        self.known
+            .iter_mut()
+            .map(|memory| PadInput {
+                fire: if std::mem::take(&mut memory.firing) {
+                    Some(false)
+                } else {
+                    None
+                },
+                ..PadInput::default()
+            })
+            .collect()
+    }
+}
+
+/// Rescales a stick axis past the dead zone to the full range, then bends it so small
+/// deflections give finer control.
+fn shape_axis(value: f32) -> f32 {
+    let magnitude = value.abs();
+    if magnitude < GAMEPAD_DEAD_ZONE {
+        return 0.0;
+    }
+    let scaled = ((magnitude - GAMEPAD_DEAD_ZONE) / (1.0 - GAMEPAD_DEAD_ZONE)).min(1.0);
+    scaled.powf(GAMEPAD_RESPONSE_EXPONENT) * value.signum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding_fire(gamepads: &mut Gamepads) {
+        gamepads.active = true;
+        gamepads.known.push(PadMemory {
+            index: 0,
+            id: "pad".to_string(),
+            firing: true,
+            shoulders: (false, false),
+        });
+    }
+
+    #[test]
+    fn the_newest_game_gets_the_pads() {
+        let first = Gamepads::new();
+        assert!(first.is_active());
+        let second = Gamepads::new();
+        assert!(second.is_active());
+        assert!(!first.is_active());
+
+        first.claim();
+        assert!(first.is_active());
+        assert!(!second.is_active());
+    }
+
+    #[test]
+    fn losing_the_pads_releases_the_trigger_once() {
+        let mut first = Gamepads::new();
+        holding_fire(&mut first);
+        let _second = Gamepads::new();
+
+        let (inputs, events) = first.poll();
+        assert!(events.is_empty());
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].fire, Some(false));
+        assert_eq!(inputs[0].drive, 0.0);
+        assert!(!first.known[0].firing);
+
+        // Nothing more until it claims them back, when the pad is still known
+        assert!(first.poll().0.is_empty());
+        assert_eq!(first.known.len(), 1);
+    }
+}
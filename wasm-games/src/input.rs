@@ -0,0 +1,319 @@
+use crate::storage::Storage;
+
+// ### Input Mapping
+
+/// Something a player can ask for, whichever device it came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    AimLeft,
+    AimRight,
+    Fire,
+    DriveLeft,
+    DriveRight,
+    Reload,
+    NextWeapon,
+    PreviousWeapon,
+    // Game-wide actions; bindings for them are kept on player one
+    CycleFilter,
+    ToggleCrosshair,
+    TogglePointerLock,
+    Continue,   // Leave the shop
+    Buy(usize), // Buy the upgrade at this position in the shop
+}
+
+/// Names of the `Buy` actions by position.
+const BUY_NAMES: [&str; 5] = ["buy-1", "buy-2", "buy-3", "buy-4", "buy-5"];
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::AimLeft,
+        Action::AimRight,
+        Action::Fire,
+        Action::DriveLeft,
+        Action::DriveRight,
+        Action::Reload,
+        Action::NextWeapon,
+        Action::PreviousWeapon,
+        Action::CycleFilter,
+        Action::ToggleCrosshair,
+        Action::TogglePointerLock,
+        Action::Continue,
+        Action::Buy(0),
+        Action::Buy(1),
+        Action::Buy(2),
+        Action::Buy(3),
+        Action::Buy(4),
+    ];
+
+    /// Name used by JavaScript and in saved bindings.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::AimLeft => "aim-left",
+            Action::AimRight => "aim-right",
+            Action::Fire => "fire",
+            Action::DriveLeft => "drive-left",
+            Action::DriveRight => "drive-right",
+            Action::Reload => "reload",
+            Action::NextWeapon => "next-weapon",
+            Action::PreviousWeapon => "previous-weapon",
+            Action::CycleFilter => "cycle-filter",
+            Action::ToggleCrosshair => "toggle-crosshair",
+            Action::TogglePointerLock => "toggle-pointer-lock",
+            Action::Continue => "continue",
+            Action::Buy(index) => BUY_NAMES[index],
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+}
+
+/// One key bound to one player's action.
+///
+/// Keys are named like winit's `VirtualKeyCode` variants ("Left", "Space", "A", "Key1"), so the
+/// mapping itself doesn't depend on winit.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Binding {
+    pub player: usize,
+    pub action: Action,
+    pub key: String,
+}
+
+impl Binding {
+    fn new(player: usize, action: Action, key: &str) -> Self {
+        Binding {
+            player,
+            action,
+            key: key.to_string(),
+        }
+    }
+}
+
+/// Maps keys to player actions. An action may have any number of keys, and each player's key
+/// triggers at most one of their actions.
+pub struct InputMap {
+    players: usize,
+    bindings: Vec<Binding>,
+}
+
+impl InputMap {
+    /// Built-in bindings for one or two players.
+    ///
+    /// Alone: arrow keys and Space to aim and fire, A/D to drive, R to reload and Q/E to switch
+    /// weapons. In co-op player one keeps the arrow keys, Space and R, switching weapons with
+    /// right Shift and right Ctrl, while player two aims with A/D, fires with W, reloads with S and
+    /// switches with Q/E. Nobody drives from the keyboard in co-op. Either way the number keys
+    /// buy upgrades in the shop.
+    pub fn defaults(players: usize) -> Self {
+        let mut bindings = vec![
+            Binding::new(0, Action::AimLeft, "Left"),
+            Binding::new(0, Action::AimRight, "Right"),
+            Binding::new(0, Action::Fire, "Space"),
+            Binding::new(0, Action::Reload, "R"),
+            Binding::new(0, Action::CycleFilter, "V"),
+            Binding::new(0, Action::ToggleCrosshair, "C"),
+            Binding::new(0, Action::TogglePointerLock, "L"),
+            Binding::new(0, Action::Continue, "Return"),
+            Binding::new(0, Action::Buy(0), "Key1"),
+            Binding::new(0, Action::Buy(1), "Key2"),
+            Binding::new(0, Action::Buy(2), "Key3"),
+            Binding::new(0, Action::Buy(3), "Key4"),
+            Binding::new(0, Action::Buy(4), "Key5"),
+        ];
+        if players > 1 {
+            bindings.extend(vec![
+                Binding::new(0, Action::NextWeapon, "RShift"),
+                Binding::new(0, Action::PreviousWeapon, "RControl"),
+                Binding::new(1, Action::AimLeft, "A"),
+                Binding::new(1, Action::AimRight, "D"),
+                Binding::new(1, Action::Fire, "W"),
+                Binding::new(1, Action::Reload, "S"),
+                Binding::new(1, Action::NextWeapon, "E"),
+                Binding::new(1, Action::PreviousWeapon, "Q"),
+            ]);
+        } else {
+            bindings.extend(vec![
+                Binding::new(0, Action::DriveLeft, "A"),
+                Binding::new(0, Action::DriveRight, "D"),
+                Binding::new(0, Action::NextWeapon, "E"),
+                Binding::new(0, Action::PreviousWeapon, "Q"),
+            ]);
+        }
+        InputMap { players, bindings }
+    }
+
+    /// Bindings saved under `key`, or the defaults if none were saved.
+    pub fn load(storage: &dyn Storage, key: &str, players: usize) -> Self {
+        let saved = match storage.load(key) {
+            Some(saved) => saved,
+            None => return InputMap::defaults(players),
+        };
+        let mut map = InputMap {
+            players,
+            bindings: Vec::new(),
+        };
+        // One "player action=key" per line; lines that don't parse are skipped
+        for line in saved.lines() {
+            let mut parts = line.splitn(2, '=');
+            let (target, key) = match (parts.next(), parts.next()) {
+                (Some(target), Some(key)) => (target.trim(), key.trim()),
+                _ => continue,
+            };
+            let mut target = target.split_whitespace();
+            let player = target
+                .next()
+                .and_then(|player| player.parse::<usize>().ok());
+            let action = target.next().and_then(Action::by_name);
+            if let (Some(player), Some(action)) = (player, action) {
+                map.bind(player, action, key);
+            }
+        }
+        map
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage, key: &str) {
+        let mut saved = String::new();
+        for binding in &self.bindings {
+            saved.push_str(&format!(
+                "{} {}={}\n",
+                binding.player,
+                binding.action.name(),
+                binding.key
+            ));
+        }
+        storage.save(key, &saved);
+    }
+
+    /// The player and action `key` triggers for each player it is bound for.
+    pub fn actions_for<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (usize, Action)> + 'a {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.key == key)
+            .map(|binding| (binding.player, binding.action))
+    }
+
+    /// Adds `key` to a player's action, taking it away from any other action of theirs.
+    /// Returns false if there is no such player or the key is empty.
+    pub fn bind(&mut self, player: usize, action: Action, key: &str) -> bool {
+        if player >= self.players || key.is_empty() {
+            return false;
+        }
+        self.bindings
+            .retain(|binding| binding.player != player || binding.key != key);
+        self.bindings.push(Binding::new(player, action, key));
+        true
+    }
+
+    /// Removes every key from a player's action.
+    pub fn clear(&mut self, player: usize, action: Action) {
+        self.bindings
+            .retain(|binding| binding.player != player || binding.action != action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    const KEY: &str = "bindings";
+
+    fn actions(map: &InputMap, key: &str) -> Vec<(usize, Action)> {
+        map.actions_for(key).collect()
+    }
+
+    #[test]
+    fn single_player_defaults() {
+        let map = InputMap::defaults(1);
+        assert_eq!(actions(&map, "Space"), vec![(0, Action::Fire)]);
+        assert_eq!(actions(&map, "A"), vec![(0, Action::DriveLeft)]);
+        assert_eq!(actions(&map, "Key3"), vec![(0, Action::Buy(2))]);
+        assert!(map.bindings.iter().all(|binding| binding.player == 0));
+    }
+
+    #[test]
+    fn co_op_defaults() {
+        let map = InputMap::defaults(2);
+        assert_eq!(actions(&map, "Space"), vec![(0, Action::Fire)]);
+        assert_eq!(actions(&map, "A"), vec![(1, Action::AimLeft)]);
+        assert_eq!(actions(&map, "W"), vec![(1, Action::Fire)]);
+        // Nobody drives from the keyboard in co-op
+        let drives = |binding: &Binding| {
+            binding.action == Action::DriveLeft || binding.action == Action::DriveRight
+        };
+        assert!(!map.bindings.iter().any(drives));
+    }
+
+    #[test]
+    fn bind_steals_the_key_from_the_same_player() {
+        let mut map = InputMap::defaults(2);
+        assert!(map.bind(0, Action::Reload, "Space"));
+        assert_eq!(actions(&map, "Space"), vec![(0, Action::Reload)]);
+        // Other players keep their own binding for the key
+        assert!(map.bind(1, Action::Fire, "Space"));
+        assert_eq!(
+            actions(&map, "Space"),
+            vec![(0, Action::Reload), (1, Action::Fire)]
+        );
+        // The action keeps its other keys
+        assert_eq!(actions(&map, "R"), vec![(0, Action::Reload)]);
+    }
+
+    #[test]
+    fn bind_rejects_empty_keys_and_unknown_players() {
+        let mut map = InputMap::defaults(1);
+        assert!(!map.bind(0, Action::Fire, ""));
+        assert!(!map.bind(1, Action::Fire, "F"));
+        assert!(actions(&map, "F").is_empty());
+    }
+
+    #[test]
+    fn clear_removes_every_key_of_the_action() {
+        let mut map = InputMap::defaults(1);
+        map.bind(0, Action::Fire, "F");
+        map.clear(0, Action::Fire);
+        assert!(actions(&map, "Space").is_empty());
+        assert!(actions(&map, "F").is_empty());
+        assert_eq!(actions(&map, "R"), vec![(0, Action::Reload)]);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut storage = MemoryStorage::default();
+        let mut map = InputMap::defaults(2);
+        map.bind(1, Action::Buy(0), "Numpad1");
+        map.clear(0, Action::Reload);
+        map.save(&mut storage, KEY);
+
+        let loaded = InputMap::load(&storage, KEY, 2);
+        assert_eq!(loaded.bindings, map.bindings);
+    }
+
+    #[test]
+    fn load_falls_back_to_defaults() {
+        let storage = MemoryStorage::default();
+        let map = InputMap::load(&storage, KEY, 1);
+        assert_eq!(map.bindings, InputMap::defaults(1).bindings);
+    }
+
+    #[test]
+    fn load_skips_malformed_lines() {
+        let mut storage = MemoryStorage::default();
+        storage.save(
+            KEY,
+            "0 fire=Space\nnonsense\n0 fly=F\nx fire=G\n0 reload\n1 fire=W\n0 buy-2=Key2\n",
+        );
+        let map = InputMap::load(&storage, KEY, 1);
+        assert_eq!(
+            map.bindings,
+            vec![
+                Binding::new(0, Action::Fire, "Space"),
+                Binding::new(0, Action::Buy(1), "Key2"),
+            ]
+        );
+    }
+}
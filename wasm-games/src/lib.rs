@@ -1,1009 +1,608 @@
-use glow::HasContext;
-use js_sys::{Date, Math};
-use std::collections::HashSet;
+use js_sys::Date;
+use std::cell::Cell;
 use std::panic;
 use wasm_bindgen::prelude::*;
-use web_sys::Element;
-use winit::event::{ElementState, Event, TouchPhase, VirtualKeyCode, WindowEvent};
-use winit::event_loop::{ControlFlow, EventLoop};
+use web_sys::{Element, HtmlCanvasElement};
+use winit::event::{ElementState, Event, TouchPhase, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopProxy};
+#[cfg(target_arch = "wasm32")]
 use winit::platform::web::WindowExtWebSys;
-use winit::window::WindowBuilder;
-
-// ### Constants
-const CANNON_WIDTH: f32 = 0.05;
-const CANNON_LENGTH: f32 = 0.1;
-const CANNON_Y: f32 = -0.9;
-const BALL_SIZE: f32 = 0.02;
-const BALL_RADIUS: f32 = BALL_SIZE / 2.0;
-const BALL_SPEED: f32 = 1.0;
-const BRICK_WIDTH: f32 = 0.1;
-const BRICK_HEIGHT: f32 = 0.1;
-const BRICK_SPACING: f32 = 0.01;
-const ROTATION_SPEED: f32 = 2.0 * std::f32::consts::PI;
-const FIRE_COOLDOWN: f32 = 0.01;
-const FRICTION: f32 = 0.9;
-const MAX_OFFSET: f32 = 0.02; // Maximum position offset for bricks
-const HIT_EFFECT_DURATION: f32 = 0.1; // Duration of hit flash in seconds
-const PARTICLE_LIFETIME: f32 = 0.5; // Particle lifespan in seconds
-const PARTICLE_SPEED: f32 = 0.2; // Base speed for particles
-const GROUND_TOP: f32 = -0.95; // Top y-coordinate of ground
-const GROUND_COLOR: [f32; 4] = [0.3, 0.2, 0.1, 1.0]; // Brown ground color
-const BASE_WIDTH: f32 = 0.1; // Cannon base width
-const BASE_HEIGHT: f32 = 0.05; // Cannon base height
-const BASE_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 1.0]; // Dark gray base
-const BARREL_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0]; // Light gray barrel
-const BALL_COLOR: [f32; 4] = [0.0, 1.0, 0.0, 1.0]; // Green bullets
-
-// ### Structures
-
-/// Represents a brick with position, health, color, and hit effect timer.
-struct Brick {
-    left: f32,
-    bottom: f32,
-    right: f32,
-    top: f32,
-    health: u32,
-    base_color: [f32; 4], // Base color based on position
-    hit_timer: f32,       // Timer for hit effect
-}
-
-/// Represents a bullet fired from the cannon.
-struct Bullet {
-    x: f32,
-    y: f32,
-    dx: f32,
-    dy: f32,
-    radius: f32,
-    health: u32,
-}
+use winit::window::{Window, WindowBuilder};
+
+pub mod atlas;
+mod audio;
+mod breakout;
+pub mod camera;
+mod cannon;
+mod config;
+mod display;
+mod error;
+mod events;
+mod game;
+mod gamepad;
+mod input;
+mod mouse;
+mod particles;
+mod player;
+mod playfield;
+mod pointer;
+mod powerups;
+mod random;
+pub mod renderer;
+mod shop;
+mod storage;
+mod text;
+mod theme;
+mod touch;
+mod upgrades;
+mod weapons;
+
+use audio::Audio;
+use camera::Camera;
+use config::GameConfig;
+use display::Display;
+use error::InitError;
+use events::{EventDispatcher, EVENT_NAMES};
+use game::{BindingRequest, Engine, GameInfo, GameInput, GameSetup, Setting, TouchRequest};
+use gamepad::Gamepads;
+use input::Action;
+use playfield::WORLD_WIDTH;
+use pointer::TouchPointers;
+use renderer::Renderer;
+use renderer::WebGlRenderer;
+use theme::Theme;
+use touch::TouchScheme;
 
-/// Represents a particle for visual effects.
-struct Particle {
-    x: f32,
-    y: f32,
-    dx: f32,
-    dy: f32,
-    lifetime: f32,
-    color: [f32; 4],
-}
-
-/// HashGrid for efficient collision detection.
-struct HashGrid {
-    cell_width: f32,
-    cell_height: f32,
-    grid: Vec<Vec<usize>>,
-    grid_width: usize,
-    grid_height: usize,
-}
+// ### Main Function
 
-impl HashGrid {
-    fn new(cell_width: f32, cell_height: f32, grid_width: usize, grid_height: usize) -> Self {
-        HashGrid {
-            cell_width,
-            cell_height,
-            grid: vec![vec![]; grid_width * grid_height],
-            grid_width,
-            grid_height,
+/// Sets up the engine and `game` and starts their loop, returning a handle to control it or
+/// the reason it can't run here.
+pub fn main_with_container(
+    container: Element,
+    game: &GameInfo,
+    config: GameConfig,
+) -> Result<GameHandle, InitError> {
+    let event_loop = EventLoop::with_user_event();
+    let proxy = event_loop.create_proxy();
+    let window = WindowBuilder::new()
+        .with_title(game.title)
+        .build(&event_loop)
+        .map_err(|error| InitError::Dom(error.to_string()))?;
+    let web_window = web_sys::window().ok_or_else(|| InitError::Dom("no window".to_string()))?;
+
+    // Set up WebGL before touching the page, so failing leaves it as it was. The renderer is
+    // sized for the real canvas once that is mounted.
+    let (canvas, gl) = canvas_with_webgl2(&window)?;
+    let mut camera = Camera::new(config.world_width, config.world_height, config.fit_mode);
+    let mut renderer = WebGlRenderer::new(gl, &camera).map_err(InitError::Renderer)?;
+
+    // Mount the canvas inside the container, filling it; the page's own styles are left alone
+    container
+        .append_child(&canvas)
+        .map_err(|_| InitError::Dom("cannot append the canvas".to_string()))?;
+    // Games may centre panels over the canvas, so the container must be their containing block
+    let container_position = web_window
+        .get_computed_style(&container)
+        .ok()
+        .flatten()
+        .and_then(|style| style.get_property_value("position").ok());
+    let mut positioned_container = None; // Container and its inline position, to undo later
+    if container_position.as_deref() == Some("static") {
+        if let Some(container) = container.dyn_ref::<web_sys::HtmlElement>() {
+            let style = container.style();
+            let inline_position = style.get_property_value("position").unwrap_or_default();
+            style.set_property("position", "relative").unwrap();
+            positioned_container = Some((container.clone(), inline_position));
         }
     }
 
-    fn get_cell_index(&self, x: f32, y: f32) -> (i32, i32) {
-        let i_x = ((x + 1.0) / self.cell_width).floor() as i32;
-        let i_y = ((y + 1.0) / self.cell_height).floor() as i32;
-        (i_x, i_y)
-    }
+    canvas.style().set_property("width", "100%").unwrap();
+    canvas.style().set_property("height", "100%").unwrap();
+    canvas.style().set_property("display", "block").unwrap();
+    canvas.style().set_property("touch-action", "none").unwrap();
+    canvas
+        .style()
+        .set_property("-webkit-user-select", "none")
+        .unwrap();
+    canvas
+        .style()
+        .set_property("-moz-user-select", "none")
+        .unwrap();
+    canvas
+        .style()
+        .set_property("-ms-user-select", "none")
+        .unwrap();
+    canvas.style().set_property("user-select", "none").unwrap();
+    canvas.focus().unwrap(); // Give the canvas focus
+
+    // Size the backing store for the screen's pixel density
+    let display = Display::new(canvas.clone(), &container, config.render_scale_cap());
+    let (initial_width, initial_height) = display.fit_canvas();
+    camera.resize(initial_width, initial_height);
+    renderer.set_camera(&camera);
+
+    let mut engine = Engine {
+        camera,
+        display,
+        storage: storage::open_storage(),
+        audio: Audio::open(),
+        events: Vec::new(),
+    };
+    let players = game.players;
+    let mut game = (game.create)(GameSetup {
+        container: &container,
+        canvas: &canvas,
+        config,
+        storage: engine.storage.as_ref(),
+    });
 
-    fn add_brick(&mut self, brick_index: usize, left: f32, bottom: f32, right: f32, top: f32) {
-        let (i_x_min, i_y_min) = self.get_cell_index(left, bottom);
-        let (i_x_max, i_y_max) = self.get_cell_index(right, top);
-        for i_x in i_x_min..=i_x_max {
-            for i_y in i_y_min..=i_y_max {
-                if i_x >= 0
-                    && i_x < self.grid_width as i32
-                    && i_y >= 0
-                    && i_y < self.grid_height as i32
-                {
-                    let index = (i_x as usize) * self.grid_height + (i_y as usize);
-                    self.grid[index].push(brick_index);
+    let mounted_canvas = canvas.clone();
+    let loop_positioned_container = positioned_container.clone();
+    let mut gamepads = Gamepads::new();
+    let touch_pointers = TouchPointers::new(&container, canvas.clone(), engine.audio.unlocker());
+    let mut events = EventDispatcher::new(container);
+    let mut last_time = Date::now();
+    let mut paused = false;
+
+    // winit's run never returns: it throws to unwind once the loop is installed. Starting it
+    // from a timeout lets initialization return its result to JavaScript first.
+    let run_loop: Closure<dyn FnMut()> = Closure::once::<_, _, ()>(move || {
+        event_loop.run(move |event, _, control_flow| {
+            // Dropping the loop's state with this closure releases the game, renderer and
+            // listeners; only the canvas and the container's style must be undone by hand
+            if let Event::LoopDestroyed = event {
+                game.save(&mut engine);
+                canvas.remove();
+                restore_container_position(&loop_positioned_container);
+                return;
+            }
+            if *control_flow == ControlFlow::Exit {
+                return; // Destroyed earlier in this iteration
+            }
+            // While paused, only wake up for events
+            *control_flow = if paused {
+                ControlFlow::Wait
+            } else {
+                ControlFlow::Poll
+            };
+
+            match event {
+                Event::UserEvent(command) => match command {
+                    Command::Pause => {
+                        paused = true;
+                        *control_flow = ControlFlow::Wait;
+                        game.pause();
+                    }
+                    Command::Resume => {
+                        paused = false;
+                        last_time = Date::now(); // Don't count the pause as one long frame
+                    }
+                    Command::Reset => {
+                        game.restart(&mut engine);
+                        window.request_redraw();
+                    }
+                    Command::Destroy => *control_flow = ControlFlow::Exit,
+                    Command::Configure(setting) => {
+                        if let Setting::Config(config) = &setting {
+                            engine.camera.set_world(
+                                config.world_width,
+                                config.world_height,
+                                config.fit_mode,
+                            );
+                            engine
+                                .display
+                                .set_max_render_scale(config.render_scale_cap());
+                            window.request_redraw();
+                        }
+                        game.configure(&mut engine, setting);
+                    }
+                    Command::Subscribe(name, callback) => events.subscribe(name, callback),
+                    Command::Serialize(resolve) => {
+                        let snapshot = JsValue::from_str(&game.serialize());
+                        let _ = resolve.call1(&JsValue::NULL, &snapshot);
+                    }
+                    Command::LoadAtlas(url) => {
+                        renderer.load_atlas_image(&url);
+                        window.request_redraw();
+                    }
+                },
+                Event::WindowEvent { event, .. } => {
+                    let input = match event {
+                        WindowEvent::CloseRequested => {
+                            *control_flow = ControlFlow::Exit;
+                            None
+                        }
+                        WindowEvent::Resized(_) => {
+                            // winit sizes the canvas in pixels on fullscreen and zoom changes;
+                            // let it fill the container again and refit the backing store
+                            canvas.style().set_property("width", "100%").unwrap();
+                            canvas.style().set_property("height", "100%").unwrap();
+                            engine.display.mark_resized();
+                            None
+                        }
+                        WindowEvent::CursorMoved { position, .. } => {
+                            let (cursor_x, cursor_y) = engine.display.to_canvas_pixels(position);
+                            let (x, y) = engine.camera.unproject(cursor_x, cursor_y);
+                            Some(GameInput::CursorMoved { x, y })
+                        }
+                        WindowEvent::CursorLeft { .. } => Some(GameInput::CursorLeft),
+                        WindowEvent::Focused(true) => {
+                            gamepads.claim();
+                            None
+                        }
+                        WindowEvent::MouseInput { state, button, .. } => {
+                            let pressed = state == ElementState::Pressed;
+                            if pressed {
+                                engine.audio.unlock();
+                                gamepads.claim();
+                            }
+                            Some(GameInput::MouseButton { button, pressed })
+                        }
+                        WindowEvent::KeyboardInput { input, .. } => {
+                            input.virtual_keycode.map(|key| {
+                                let pressed = input.state == ElementState::Pressed;
+                                if pressed {
+                                    engine.audio.unlock();
+                                    gamepads.claim();
+                                }
+                                GameInput::Key { key, pressed }
+                            })
+                        }
+                        _ => None,
+                    };
+                    // While paused only releases get through, so nothing stays held
+                    if let Some(input) = input {
+                        if !paused || input.is_release() {
+                            game.handle_input(&mut engine, input);
+                        }
+                    }
+                }
+                Event::MainEventsCleared => {
+                    if let Some((width, height)) = engine.display.take_resize() {
+                        engine.camera.resize(width, height);
+                        if paused {
+                            window.request_redraw(); // Keep the frozen frame sharp
+                        }
+                    }
+                    for touch in touch_pointers.take() {
+                        if touch.phase == TouchPhase::Started {
+                            gamepads.claim();
+                        }
+                        let (touch_x, touch_y) =
+                            engine.display.css_to_canvas_pixels(touch.x, touch.y);
+                        let (x, y) = engine.camera.unproject(touch_x, touch_y);
+                        let input = GameInput::Touch {
+                            id: touch.id,
+                            phase: touch.phase,
+                            x,
+                            y,
+                        };
+                        if !paused || input.is_release() {
+                            game.handle_input(&mut engine, input);
+                        }
+                    }
+                    if paused {
+                        return;
+                    }
+                    let current_time = Date::now();
+                    let delta_time = (current_time - last_time) / 1000.0;
+                    last_time = current_time;
+                    let (pads, pad_events) = gamepads.poll();
+                    for event in pad_events {
+                        game.handle_input(&mut engine, GameInput::GamepadConnection(event));
+                    }
+                    game.handle_input(&mut engine, GameInput::Gamepads(&pads));
+                    game.update(&mut engine, delta_time as f32);
+                    for event in engine.events.drain(..) {
+                        events.dispatch(&event);
+                    }
+                    window.request_redraw();
                 }
+                Event::RedrawRequested(_) => {
+                    if engine.display.take_context_restored() {
+                        match renderer.restore(&engine.camera) {
+                            Ok(()) => log::info!("WebGL context restored"),
+                            Err(error) => log::error!("Cannot restore the renderer: {}", error),
+                        }
+                    }
+                    if engine.display.is_context_lost() {
+                        return;
+                    }
+                    game.render(&mut renderer, &mut engine.camera);
+                    renderer.present();
+                }
+                _ => {}
             }
-        }
-    }
-
-    fn get_bricks_in_cell(&self, i_x: i32, i_y: i32) -> &[usize] {
-        if i_x >= 0 && i_x < self.grid_width as i32 && i_y >= 0 && i_y < self.grid_height as i32 {
-            let index = (i_x as usize) * self.grid_height + (i_y as usize);
-            &self.grid[index]
-        } else {
-            &[]
-        }
+        });
+    });
+    if web_window
+        .set_timeout_with_callback(run_loop.as_ref().unchecked_ref())
+        .is_err()
+    {
+        // Dropping the closure releases the game, with anything it added, and the renderer
+        drop(run_loop);
+        mounted_canvas.remove();
+        restore_container_position(&positioned_container);
+        return Err(InitError::Dom("cannot schedule the game loop".to_string()));
     }
+    run_loop.forget();
+    Ok(GameHandle {
+        proxy,
+        config: Cell::new(config),
+        destroyed: Cell::new(false),
+        players,
+    })
 }
 
-// ### Weapon Trait and Implementation
-
-trait Weapon {
-    fn update(&mut self, delta_time: f32);
-    fn try_fire(&mut self, cannon_position: (f32, f32), cannon_angle: f32) -> Option<Bullet>;
+/// Puts back the container's inline position if mounting the canvas changed it.
+fn restore_container_position(positioned_container: &Option<(web_sys::HtmlElement, String)>) {
+    if let Some((container, inline_position)) = positioned_container {
+        let _ = container.style().set_property("position", inline_position);
+    }
 }
 
-/// Simple cannon implementation with cooldown and firing variation.
-struct SimpleCannon {
-    cooldown_timer: f32,
-    cooldown_min: f32,
-    cooldown_max: f32,
-    angle_spread: f32,
-    speed_min: f32,
-    speed_max: f32,
-    bullet_radius: f32,
+/// winit's canvas for `window` and a WebGL2 context on it.
+#[cfg(target_arch = "wasm32")]
+fn canvas_with_webgl2(window: &Window) -> Result<(HtmlCanvasElement, glow::Context), InitError> {
+    let canvas = window.canvas();
+    let webgl2_context = canvas
+        .get_context("webgl2")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into::<web_sys::WebGl2RenderingContext>().ok())
+        .ok_or(InitError::WebGl2Unavailable)?;
+    Ok((canvas, glow::Context::from_webgl2_context(webgl2_context)))
 }
 
-impl SimpleCannon {
-    fn new() -> Self {
-        SimpleCannon {
-            cooldown_timer: 0.0,
-            cooldown_min: 0.005,
-            cooldown_max: 0.015,
-            angle_spread: 0.1,
-            speed_min: 0.8 * BALL_SPEED,
-            speed_max: 1.2 * BALL_SPEED,
-            bullet_radius: BALL_RADIUS,
-        }
-    }
+/// Windows only have a canvas in the browser.
+#[cfg(not(target_arch = "wasm32"))]
+fn canvas_with_webgl2(_window: &Window) -> Result<(HtmlCanvasElement, glow::Context), InitError> {
+    Err(InitError::Dom("not running in a browser".to_string()))
 }
 
-impl Weapon for SimpleCannon {
-    fn update(&mut self, delta_time: f32) {
-        self.cooldown_timer = (self.cooldown_timer - delta_time).max(0.0);
-    }
-
-    fn try_fire(&mut self, cannon_position: (f32, f32), cannon_angle: f32) -> Option<Bullet> {
-        if self.cooldown_timer > 0.0 {
-            return None;
-        }
+// ### WebAssembly Bindings
 
-        let (cannon_x, cannon_y) = cannon_position;
-        let random_angle_offset = (Math::random() as f32 * 2.0 - 1.0) * self.angle_spread;
-        let firing_angle = cannon_angle + random_angle_offset;
-        let random_speed =
-            self.speed_min + Math::random() as f32 * (self.speed_max - self.speed_min);
-        let dx = -random_speed * firing_angle.sin();
-        let dy = random_speed * firing_angle.cos();
-
-        let tip_x = cannon_x + CANNON_LENGTH * firing_angle.sin();
-        let tip_y = cannon_y + CANNON_LENGTH * firing_angle.cos();
-        let bullet_x = -tip_x;
-        let bullet_y = tip_y;
-
-        self.cooldown_timer =
-            self.cooldown_min + Math::random() as f32 * (self.cooldown_max - self.cooldown_min);
-
-        Some(Bullet {
-            x: bullet_x,
-            y: bullet_y,
-            dx,
-            dy,
-            radius: self.bullet_radius,
-            health: 1,
-        })
-    }
+/// Something JavaScript asked a running game to do, delivered to its loop as a user event.
+enum Command {
+    Pause,
+    Resume,
+    Reset,
+    Destroy,
+    Configure(Setting),
+    Subscribe(&'static str, js_sys::Function),
+    Serialize(js_sys::Function), // Called with the snapshot
+    LoadAtlas(String),           // Image URL
 }
 
-// ### Game State
-
-struct GameState {
-    cannon_x: f32,
-    cannon_y: f32,
-    theta: f32,
-    bullets: Vec<Bullet>,
-    bricks: Vec<Brick>,
-    particles: Vec<Particle>,
-    left_pressed: bool,
-    right_pressed: bool,
-    fire_pressed: bool,
-    weapon: SimpleCannon,
-    hash_grid: HashGrid,
-    window_width: f32,
-    window_height: f32,
-    touch_id: Option<u64>,
-    score: u32,
+/// A running game, returned by `initialize`. Each game on a page has its own handle.
+///
+/// Commands are queued for the game's loop, so they take effect on its next update. Once
+/// `destroy` is called, further commands are ignored and `serialize` rejects.
+#[wasm_bindgen]
+pub struct GameHandle {
+    proxy: EventLoopProxy<Command>,
+    config: Cell<GameConfig>, // As last sent to the loop
+    destroyed: Cell<bool>,    // The web proxy still accepts events after the loop has exited
+    players: usize,
 }
 
-impl GameState {
-    fn new() -> Self {
-        let mut bricks = Vec::new();
-        let num_rows = 10;
-        let num_cols = 10;
-        let span_x = (num_cols - 1) as f32 * (BRICK_WIDTH + BRICK_SPACING) + BRICK_WIDTH;
-        let starting_x = -span_x / 2.0;
-        let starting_y =
-            1.0 - BRICK_HEIGHT - (num_rows - 1) as f32 * (BRICK_HEIGHT + BRICK_SPACING);
-
-        for i in 0..num_rows {
-            for j in 0..num_cols {
-                let offset_x = (Math::random() as f32 * 2.0 - 1.0) * MAX_OFFSET;
-                let offset_y = (Math::random() as f32 * 2.0 - 1.0) * MAX_OFFSET;
-                let x = starting_x + (j as f32) * (BRICK_WIDTH + BRICK_SPACING) + offset_x;
-                let y = starting_y + (i as f32) * (BRICK_HEIGHT + BRICK_SPACING) + offset_y;
-                let hue = ((x + 1.0) / 2.0) * 360.0;
-                let color = hsv_to_rgb(hue, 0.7, 1.0);
-                bricks.push(Brick {
-                    left: x,
-                    bottom: y,
-                    right: x + BRICK_WIDTH,
-                    top: y + BRICK_HEIGHT,
-                    health: 5,
-                    base_color: [color[0], color[1], color[2], 1.0],
-                    hit_timer: 0.0,
-                });
-            }
-        }
-
-        let grid_width = (2.0 / BRICK_WIDTH).ceil() as usize;
-        let grid_height = (2.0 / BRICK_HEIGHT).ceil() as usize;
-        let mut hash_grid = HashGrid::new(BRICK_WIDTH, BRICK_HEIGHT, grid_width, grid_height);
-
-        for (index, brick) in bricks.iter().enumerate() {
-            hash_grid.add_brick(index, brick.left, brick.bottom, brick.right, brick.top);
-        }
-
-        GameState {
-            cannon_x: 0.0,
-            cannon_y: CANNON_Y,
-            theta: 0.0,
-            bullets: Vec::new(),
-            bricks,
-            particles: Vec::new(),
-            left_pressed: false,
-            right_pressed: false,
-            fire_pressed: false,
-            weapon: SimpleCannon::new(),
-            hash_grid,
-            window_width: 0.0,
-            window_height: 0.0,
-            touch_id: None,
-            score: 0,
+impl GameHandle {
+    fn check_player(&self, player: usize) -> Result<(), JsValue> {
+        if player >= self.players {
+            return Err(JsValue::from_str(&format!("Unknown player: {}", player)));
         }
+        Ok(())
     }
 
-    fn update(&mut self, delta_time: f32) {
-        // Handle cannon rotation via keyboard
-        if self.touch_id.is_none() {
-            if self.left_pressed {
-                self.theta += ROTATION_SPEED * delta_time;
-            }
-            if self.right_pressed {
-                self.theta -= ROTATION_SPEED * delta_time;
-            }
+    fn send(&self, command: Command) {
+        if self.destroyed.get() {
+            return;
         }
-        self.theta = self
-            .theta
-            .clamp(-std::f32::consts::PI / 2.0, std::f32::consts::PI / 2.0);
-
-        // Update weapon and fire bullets
-        self.weapon.update(delta_time);
-        if self.fire_pressed {
-            if let Some(bullet) = self
-                .weapon
-                .try_fire((self.cannon_x, self.cannon_y), self.theta)
-            {
-                self.bullets.push(bullet);
-                let tip_x = self.cannon_x + CANNON_LENGTH * self.theta.sin();
-                let tip_y = self.cannon_y + CANNON_LENGTH * self.theta.cos();
-                self.spawn_particles(-tip_x, tip_y, 5, [1.0, 0.5, 0.0, 1.0]);
-            }
-        }
-
-        // Update bullets and handle collisions
-        let mut to_spawn = Vec::new();
-        for bullet in &mut self.bullets {
-            let friction_factor = FRICTION.powf(delta_time);
-            bullet.dx *= friction_factor;
-            bullet.dy *= friction_factor;
-
-            bullet.x += bullet.dx * delta_time;
-            bullet.y += bullet.dy * delta_time;
-
-            // Bounce off walls
-            if bullet.x - bullet.radius < -1.0 {
-                bullet.x = -1.0 + bullet.radius;
-                bullet.dx = -bullet.dx;
-            }
-            if bullet.x + bullet.radius > 1.0 {
-                bullet.x = 1.0 - bullet.radius;
-                bullet.dx = -bullet.dx;
-            }
-            if bullet.y + bullet.radius > 1.0 {
-                bullet.y = 1.0 - bullet.radius;
-                bullet.dy = -bullet.dy;
-            }
-
-            let bullet_left = bullet.x - bullet.radius;
-            let bullet_right = bullet.x + bullet.radius;
-            let bullet_bottom = bullet.y - bullet.radius;
-            let bullet_top = bullet.y + bullet.radius;
-
-            let (i_x_min, i_y_min) = self.hash_grid.get_cell_index(bullet_left, bullet_bottom);
-            let (i_x_max, i_y_max) = self.hash_grid.get_cell_index(bullet_right, bullet_top);
-
-            let mut bricks_to_check = HashSet::new();
-            for i_x in i_x_min..=i_x_max {
-                for i_y in i_y_min..=i_y_max {
-                    let bricks_in_cell = self.hash_grid.get_bricks_in_cell(i_x, i_y);
-                    for &brick_index in bricks_in_cell {
-                        bricks_to_check.insert(brick_index);
-                    }
-                }
-            }
-
-            for brick_index in bricks_to_check {
-                let brick = &mut self.bricks[brick_index];
-                if brick.health > 0
-                    && bullet_right > brick.left
-                    && bullet_left < brick.right
-                    && bullet_top > brick.bottom
-                    && bullet_bottom < brick.top
-                {
-                    brick.health -= 1;
-                    bullet.health -= 1;
-                    bullet.dy = -bullet.dy;
-                    brick.hit_timer = HIT_EFFECT_DURATION;
-                    to_spawn.push((bullet.x, bullet.y, 3, brick.base_color));
-                    if brick.health == 0 {
-                        self.score += 1;
-                        let document = web_sys::window().unwrap().document().unwrap();
-                        if let Some(score_element) = document.get_element_by_id("score") {
-                            score_element.set_inner_html(&format!("Score: {}", self.score));
-                        }
-                        let brick_center_x = (brick.left + brick.right) / 2.0;
-                        let brick_center_y = (brick.bottom + brick.top) / 2.0;
-                        to_spawn.push((brick_center_x, brick_center_y, 10, brick.base_color));
-                    }
-                }
-            }
-        }
-
-        // Spawn particles from collisions
-        for spawn in to_spawn {
-            self.spawn_particles(spawn.0, spawn.1, spawn.2, spawn.3);
-        }
-
-        // Remove dead bullets
-        self.bullets
-            .retain(|bullet| bullet.health > 0 && bullet.y - bullet.radius >= -1.0);
-
-        // Update brick hit timers
-        for brick in &mut self.bricks {
-            if brick.hit_timer > 0.0 {
-                brick.hit_timer -= delta_time;
-                if brick.hit_timer < 0.0 {
-                    brick.hit_timer = 0.0;
-                }
-            }
-        }
-
-        // Update particles
-        for particle in &mut self.particles {
-            particle.x += particle.dx * delta_time;
-            particle.y += particle.dy * delta_time;
-            particle.lifetime -= delta_time;
-        }
-        self.particles.retain(|p| p.lifetime > 0.0);
+        // Fails only once the loop has shut down, when there is nothing left to command
+        let _ = self.proxy.send_event(command);
     }
+}
 
-    fn spawn_particles(&mut self, x: f32, y: f32, count: usize, color: [f32; 4]) {
-        for _ in 0..count {
-            let angle = Math::random() as f32 * 2.0 * std::f32::consts::PI;
-            let speed = Math::random() as f32 * PARTICLE_SPEED;
-            let dx = speed * angle.cos();
-            let dy = speed * angle.sin();
-            let lifetime = PARTICLE_LIFETIME;
-            self.particles.push(Particle {
-                x,
-                y,
-                dx,
-                dy,
-                lifetime,
-                color,
-            });
-        }
+#[wasm_bindgen]
+impl GameHandle {
+    /// Freezes the game and stops drawing until `resume`.
+    pub fn pause(&self) {
+        self.send(Command::Pause);
     }
-}
 
-// ### Main Function
+    pub fn resume(&self) {
+        self.send(Command::Resume);
+    }
 
-pub fn main_with_container(container: Element) {
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new()
-        .with_title("Brick Breaker with Cannon")
-        .build(&event_loop)
-        .unwrap();
-    let canvas = window.canvas();
+    /// Starts over from stage 1. Purchased upgrades and settings are kept.
+    pub fn reset(&self) {
+        self.send(Command::Reset);
+    }
 
-    // Append canvas to the body
-    let body = web_sys::window()
-        .unwrap()
-        .document()
-        .unwrap()
-        .body()
-        .unwrap();
-    body.append_child(&canvas).expect("Append canvas to body");
+    /// Stops the loop, removes the canvas and shop with their listeners, and releases the GL
+    /// objects.
+    pub fn destroy(&self) {
+        self.send(Command::Destroy);
+        self.destroyed.set(true);
+    }
 
-    canvas.style().set_property("position", "absolute").unwrap();
-    canvas.style().set_property("top", "0").unwrap();
-    canvas.style().set_property("left", "0").unwrap();
-    canvas.style().set_property("width", "100%").unwrap();
-    canvas.style().set_property("height", "100%").unwrap();
-    canvas.style().set_property("z-index", "1").unwrap();
-    canvas.style().set_property("display", "block").unwrap();
-    canvas.style().set_property("touch-action", "none").unwrap();
-    canvas
-        .style()
-        .set_property("-webkit-user-select", "none")
-        .unwrap();
-    canvas
-        .style()
-        .set_property("-moz-user-select", "none")
-        .unwrap();
-    canvas
-        .style()
-        .set_property("-ms-user-select", "none")
-        .unwrap();
-    canvas.style().set_property("user-select", "none").unwrap();
-    canvas.focus().unwrap(); // Give the canvas focus
+    /// Switches to the built-in theme called `name`: "dark", "light", "high-contrast" or
+    /// "color-blind" (also "deuteranopia" or "protanopia").
+    pub fn set_theme(&self, name: &str) -> Result<(), JsValue> {
+        let theme = Theme::by_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown theme: {}", name)))?;
+        self.send(Command::Configure(Setting::Theme(theme)));
+        Ok(())
+    }
 
-    body.style().set_property("margin", "0").unwrap();
-    body.style().set_property("padding", "0").unwrap();
-    body.style().set_property("overflow", "hidden").unwrap();
-    body.style().set_property("position", "fixed").unwrap();
-    body.style().set_property("width", "100%").unwrap();
-    body.style().set_property("height", "100%").unwrap();
+    /// Replaces the built-in sprite atlas with the image at `url` once it has loaded. The image
+    /// must have the built-in atlas's size and layout; otherwise the built-in atlas is kept.
+    pub fn load_atlas(&self, url: &str) {
+        self.send(Command::LoadAtlas(url.to_string()));
+    }
 
-    let webgl2_context = canvas
-        .get_context("webgl2")
-        .unwrap()
-        .unwrap()
-        .dyn_into::<web_sys::WebGl2RenderingContext>()
-        .unwrap();
-    let gl = glow::Context::from_webgl2_context(webgl2_context);
-
-    // Set initial canvas size
-    let window_web = web_sys::window().unwrap();
-    let initial_width = window_web.inner_width().unwrap().as_f64().unwrap() as u32;
-    let initial_height = window_web.inner_height().unwrap().as_f64().unwrap() as u32;
-    canvas.set_width(initial_width);
-    canvas.set_height(initial_height);
-
-    unsafe {
-        gl.enable(glow::BLEND);
-        gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
-        gl.viewport(0, 0, initial_width as i32, initial_height as i32);
+    /// Changes the tunables named in `changes`, a `GameConfig` object; fields left out keep
+    /// their values. Nothing changes if any field is unknown or out of range. New brick sizes
+    /// and health apply from the next stage, weapon heat and ammunition from the next game,
+    /// everything else right away.
+    pub fn set_config(&self, changes: JsValue) -> Result<(), JsValue> {
+        let config = self
+            .config
+            .get()
+            .merged(&changes)
+            .map_err(|message| JsValue::from(js_sys::Error::new(&message)))?;
+        self.config.set(config);
+        self.send(Command::Configure(Setting::Config(config)));
+        Ok(())
     }
 
-    // Create shader program
-    let program = unsafe {
-        let vertex_array = gl
-            .create_vertex_array()
-            .expect("Cannot create vertex array");
-        gl.bind_vertex_array(Some(vertex_array));
-
-        let program = gl.create_program().expect("Cannot create program");
-        let vertex_shader_source = "#version 300 es\n
-        in vec2 position;\n
-        out vec2 v_position;\n
-        uniform mat4 model;\n
-        void main() {\n
-            gl_Position = model * vec4(position, 0.0, 1.0);\n
-            v_position = position;\n
-        }\n";
-        let fragment_shader_source = "#version 300 es\n
-        precision mediump float;\n
-        in vec2 v_position;\n
-        uniform vec4 color;\n
-        uniform bool is_background;\n
-        out vec4 fragColor;\n
-        void main() {\n
-            if (is_background) {\n
-                float t = (v_position.y + 1.0) / 2.0;\n
-                vec3 top_color = vec3(0.5, 0.7, 1.0);\n
-                vec3 bottom_color = vec3(0.2, 0.3, 0.5);\n
-                vec3 grad_color = mix(bottom_color, top_color, t);\n
-                fragColor = vec4(grad_color, 1.0);\n
-            } else {\n
-                fragColor = color;\n
-            }\n
-        }\n";
-
-        let shader_sources = [
-            (glow::VERTEX_SHADER, vertex_shader_source),
-            (glow::FRAGMENT_SHADER, fragment_shader_source),
-        ];
-
-        let mut shaders = Vec::new();
-        for (shader_type, source) in shader_sources.iter() {
-            let shader = gl
-                .create_shader(*shader_type)
-                .expect("Cannot create shader");
-            gl.shader_source(shader, source);
-            gl.compile_shader(shader);
-            if !gl.get_shader_compile_status(shader) {
-                panic!("{}", gl.get_shader_info_log(shader));
+    /// Resolves to a snapshot of the game's progress as "name=value" lines, e.g. the stage and
+    /// scores.
+    pub fn serialize(&self) -> js_sys::Promise {
+        js_sys::Promise::new(&mut |resolve, reject| {
+            if self.destroyed.get() || self.proxy.send_event(Command::Serialize(resolve)).is_err() {
+                let _ = reject.call1(&JsValue::NULL, &"The game has been destroyed".into());
             }
-            gl.attach_shader(program, shader);
-            shaders.push(shader);
-        }
+        })
+    }
 
-        gl.link_program(program);
-        if !gl.get_program_link_status(program) {
-            panic!("{}", gl.get_program_info_log(program));
-        }
+    /// The current tunables as a `GameConfig` object with every field.
+    pub fn config(&self) -> JsValue {
+        self.config.get().to_js()
+    }
 
-        for shader in shaders {
-            gl.detach_shader(program, shader);
-            gl.delete_shader(shader);
+    /// Adds `key` to `action` for `player` (0, or 1 in co-op) and saves the controls. Keys use
+    /// winit's key names such as "Left", "Space", "A" or "Key1"; a key only triggers one action
+    /// per player.
+    pub fn bind_key(&self, player: usize, action: &str, key: &str) -> Result<(), JsValue> {
+        self.check_player(player)?;
+        let action = parse_action(action)?;
+        if key.is_empty() {
+            return Err(JsValue::from_str("Key name is empty"));
         }
+        self.send(Command::Configure(Setting::Bindings(BindingRequest::Bind(
+            player,
+            action,
+            key.to_string(),
+        ))));
+        Ok(())
+    }
 
-        program
-    };
+    /// Removes every key from `action` for `player`.
+    pub fn clear_binding(&self, player: usize, action: &str) -> Result<(), JsValue> {
+        self.check_player(player)?;
+        let action = parse_action(action)?;
+        self.send(Command::Configure(Setting::Bindings(
+            BindingRequest::Clear(player, action),
+        )));
+        Ok(())
+    }
 
-    let pos_attrib = unsafe { gl.get_attrib_location(program, "position").unwrap() };
-    let color_location = unsafe { gl.get_uniform_location(program, "color").unwrap() };
-    let model_location = unsafe { gl.get_uniform_location(program, "model").unwrap() };
-    let is_background_location =
-        unsafe { gl.get_uniform_location(program, "is_background").unwrap() };
-
-    // Create score UI
-    let document = web_sys::window().unwrap().document().unwrap();
-    let score_element = document.create_element("div").unwrap();
-    score_element.set_id("score");
-    score_element.set_inner_html("Score: 0");
-    body.append_child(&score_element)
-        .expect("Append score element");
-
-    score_element.set_attribute("position", "absolute").unwrap();
-    score_element.set_attribute("top", "10px").unwrap();
-    score_element.set_attribute("left", "10px").unwrap();
-    score_element.set_attribute("color", "white").unwrap();
-    score_element.set_attribute("font-size", "24px").unwrap();
-    score_element.set_attribute("z-index", "2").unwrap();
-
-    let mut game_state = GameState::new();
-    game_state.window_width = initial_width as f32;
-    game_state.window_height = initial_height as f32;
-    let mut last_time = Date::now();
+    /// Restores the default controls.
+    pub fn reset_bindings(&self) {
+        self.send(Command::Configure(Setting::Bindings(BindingRequest::Reset)));
+    }
 
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
-
-        match event {
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                WindowEvent::Resized(physical_size) => {
-                    let width = physical_size.width;
-                    let height = physical_size.height;
-                    canvas.set_width(width);
-                    canvas.set_height(height);
-                    unsafe {
-                        gl.viewport(0, 0, width as i32, height as i32);
-                    }
-                    game_state.window_width = width as f32;
-                    game_state.window_height = height as f32;
-                }
-                WindowEvent::Touch(touch) => {
-                    let logical_position = touch.location;
-                    let touch_x = logical_position.x as f32;
-                    let touch_y = logical_position.y as f32;
-                    if game_state.window_width > 0.0 && game_state.window_height > 0.0 {
-                        let game_x = (touch_x / game_state.window_width) * 2.0 - 1.0;
-                        let game_y = 1.0 - (touch_y / game_state.window_height) * 2.0;
-                        match touch.phase {
-                            TouchPhase::Started => {
-                                game_state.touch_id = Some(touch.id);
-                                let dx = game_x - game_state.cannon_x;
-                                let dy = game_y - game_state.cannon_y;
-                                game_state.theta = dx
-                                    .atan2(dy)
-                                    .clamp(-std::f32::consts::PI / 2.0, std::f32::consts::PI / 2.0);
-                                game_state.fire_pressed = true; // Start firing immediately
-                            }
-                            TouchPhase::Moved => {
-                                if game_state.touch_id == Some(touch.id) {
-                                    let dx = game_x - game_state.cannon_x;
-                                    let dy = game_y - game_state.cannon_y;
-                                    game_state.theta = dx.atan2(dy).clamp(
-                                        -std::f32::consts::PI / 2.0,
-                                        std::f32::consts::PI / 2.0,
-                                    );
-                                    game_state.fire_pressed = true; // Continue firing while moving
-                                }
-                            }
-                            TouchPhase::Ended | TouchPhase::Cancelled => {
-                                if game_state.touch_id == Some(touch.id) {
-                                    game_state.fire_pressed = false;
-                                    game_state.touch_id = None;
-                                }
-                            }
-                        }
-                    }
-                }
-                WindowEvent::KeyboardInput { input, .. } => {
-                    if let Some(keycode) = input.virtual_keycode {
-                        match keycode {
-                            VirtualKeyCode::Left => {
-                                game_state.left_pressed = input.state == ElementState::Pressed;
-                            }
-                            VirtualKeyCode::Right => {
-                                game_state.right_pressed = input.state == ElementState::Pressed;
-                            }
-                            VirtualKeyCode::Space => {
-                                game_state.fire_pressed = input.state == ElementState::Pressed;
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-                _ => {}
-            },
-            Event::MainEventsCleared => {
-                let current_time = Date::now();
-                let delta_time = (current_time - last_time) / 1000.0;
-                last_time = current_time;
-                game_state.update(delta_time as f32);
-                window.request_redraw();
-            }
-            Event::RedrawRequested(_) => unsafe {
-                gl.clear_color(0.1, 0.2, 0.3, 1.0);
-                gl.clear(glow::COLOR_BUFFER_BIT);
-                gl.use_program(Some(program));
-
-                // Draw gradient background
-                let model_matrix = [
-                    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
-                ];
-                gl.uniform_matrix_4_f32_slice(Some(&model_location), false, &model_matrix);
-                gl.uniform_1_i32(Some(&is_background_location), 1);
-                draw_rectangle(
-                    &gl,
-                    pos_attrib,
-                    &color_location,
-                    -1.0,
-                    -1.0,
-                    1.0,
-                    1.0,
-                    [0.0, 0.0, 0.0, 0.0], // Color ignored for background
-                );
-                gl.uniform_1_i32(Some(&is_background_location), 0);
-
-                // Draw ground
-                draw_ground(&gl, pos_attrib, &color_location);
-
-                // Draw bricks
-                for brick in &game_state.bricks {
-                    if brick.health > 0 {
-                        let health_factor = brick.health as f32 / 5.0;
-                        let color = if brick.hit_timer > 0.0 {
-                            [1.0, 1.0, 1.0, 1.0] // White flash on hit
-                        } else {
-                            [
-                                brick.base_color[0] * health_factor,
-                                brick.base_color[1] * health_factor,
-                                brick.base_color[2] * health_factor,
-                                1.0,
-                            ]
-                        };
-                        let model_matrix = create_translation_matrix(brick.left, brick.bottom);
-                        gl.uniform_matrix_4_f32_slice(Some(&model_location), false, &model_matrix);
-                        draw_rectangle(
-                            &gl,
-                            pos_attrib,
-                            &color_location,
-                            0.0,
-                            0.0,
-                            BRICK_WIDTH,
-                            BRICK_HEIGHT,
-                            color,
-                        );
-                    }
-                }
+    /// Calls `callback` with the payload of every `event`: "score-changed", "brick-destroyed",
+    /// "stage-cleared", "game-over" or "weapon-changed". The same events are dispatched on the
+    /// container as bubbling `CustomEvent`s with the payload in `detail`; `GameEventMap` in the
+    /// TypeScript definitions lists the payload types.
+    pub fn on(&self, event: &str, callback: js_sys::Function) -> Result<(), JsValue> {
+        let name = EVENT_NAMES
+            .iter()
+            .copied()
+            .find(|&name| name == event)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown event: {}", event)))?;
+        self.send(Command::Subscribe(name, callback));
+        Ok(())
+    }
 
-                // Draw cannon base with supports
-                let model_matrix =
-                    create_translation_matrix(game_state.cannon_x, game_state.cannon_y);
-                gl.uniform_matrix_4_f32_slice(Some(&model_location), false, &model_matrix);
-                draw_rectangle(
-                    &gl,
-                    pos_attrib,
-                    &color_location,
-                    -BASE_WIDTH / 2.0,
-                    -BASE_HEIGHT / 2.0,
-                    BASE_WIDTH / 2.0,
-                    BASE_HEIGHT / 2.0,
-                    BASE_COLOR,
-                );
-                // Left support
-                draw_rectangle(
-                    &gl,
-                    pos_attrib,
-                    &color_location,
-                    -BASE_WIDTH / 2.0 - 0.01,
-                    -BASE_HEIGHT / 2.0,
-                    -BASE_WIDTH / 2.0,
-                    BASE_HEIGHT / 2.0,
-                    [0.3, 0.3, 0.3, 1.0],
-                );
-                // Right support
-                draw_rectangle(
-                    &gl,
-                    pos_attrib,
-                    &color_location,
-                    BASE_WIDTH / 2.0,
-                    -BASE_HEIGHT / 2.0,
-                    BASE_WIDTH / 2.0 + 0.01,
-                    BASE_HEIGHT / 2.0,
-                    [0.3, 0.3, 0.3, 1.0],
-                );
-
-                // Draw cannon barrel
-                let model_matrix =
-                    create_model_matrix(game_state.cannon_x, game_state.cannon_y, game_state.theta);
-                gl.uniform_matrix_4_f32_slice(Some(&model_location), false, &model_matrix);
-                draw_rectangle(
-                    &gl,
-                    pos_attrib,
-                    &color_location,
-                    -CANNON_WIDTH / 2.0,
-                    0.0,
-                    CANNON_WIDTH / 2.0,
-                    CANNON_LENGTH,
-                    BARREL_COLOR,
-                );
-
-                // Draw bullets
-                for bullet in &game_state.bullets {
-                    let model_matrix = create_translation_matrix(bullet.x, bullet.y);
-                    gl.uniform_matrix_4_f32_slice(Some(&model_location), false, &model_matrix);
-                    draw_circle(
-                        &gl,
-                        pos_attrib,
-                        &color_location,
-                        0.0,
-                        0.0,
-                        bullet.radius,
-                        BALL_COLOR,
-                        16,
-                    );
-                }
+    /// Switches touch controls to "drag-to-aim" (drag to aim, hold the fire button to fire),
+    /// "joystick" (drag to aim, fire button and a virtual joystick to drive) or "tap-to-fire"
+    /// (aim at and fire towards each touch).
+    pub fn set_touch_scheme(&self, name: &str) -> Result<(), JsValue> {
+        let scheme = TouchScheme::by_name(name)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown touch scheme: {}", name)))?;
+        self.send(Command::Configure(Setting::Touch(TouchRequest::Scheme(
+            scheme,
+        ))));
+        Ok(())
+    }
 
-                // Draw particles
-                for particle in &game_state.particles {
-                    let alpha = particle.lifetime / PARTICLE_LIFETIME;
-                    let color = [
-                        particle.color[0],
-                        particle.color[1],
-                        particle.color[2],
-                        alpha,
-                    ];
-                    let model_matrix = create_translation_matrix(particle.x, particle.y);
-                    gl.uniform_matrix_4_f32_slice(Some(&model_location), false, &model_matrix);
-                    draw_circle(&gl, pos_attrib, &color_location, 0.0, 0.0, 0.005, color, 8);
-                }
-            },
-            _ => {}
+    /// Sets the fraction of the joystick's radius that doesn't drive (0 to below 1) and how far
+    /// a touch moves before it aims, in world units where the playfield is 2 wide.
+    pub fn set_touch_dead_zones(&self, joystick: f32, drag: f32) -> Result<(), JsValue> {
+        if !(0.0..1.0).contains(&joystick) || !(0.0..=WORLD_WIDTH).contains(&drag) {
+            return Err(JsValue::from_str("Touch dead zone out of range"));
         }
-    });
+        self.send(Command::Configure(Setting::Touch(
+            TouchRequest::DeadZones { joystick, drag },
+        )));
+        Ok(())
+    }
 }
 
-// ### Utility Functions
-
-/// Convert HSV to RGB for brick colors.
-fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
-    let c = v * s;
-    let h_prime = (h % 360.0) / 60.0;
-    let x = c * (1.0 - (h_prime % 2.0 - 1.0).abs());
-    let (r1, g1, b1) = if h_prime < 1.0 {
-        (c, x, 0.0)
-    } else if h_prime < 2.0 {
-        (x, c, 0.0)
-    } else if h_prime < 3.0 {
-        (0.0, c, x)
-    } else if h_prime < 4.0 {
-        (0.0, x, c)
-    } else if h_prime < 5.0 {
-        (x, 0.0, c)
-    } else {
-        (c, 0.0, x)
-    };
-    let m = v - c;
-    [r1 + m, g1 + m, b1 + m]
+fn parse_action(name: &str) -> Result<Action, JsValue> {
+    Action::by_name(name).ok_or_else(|| JsValue::from_str(&format!("Unknown action: {}", name)))
 }
 
-// ### Drawing Functions
-
-fn draw_rectangle(
-    gl: &glow::Context,
-    pos_attrib: u32,
-    color_location: &glow::UniformLocation,
-    left: f32,
-    bottom: f32,
-    right: f32,
-    top: f32,
-    color: [f32; 4],
-) {
-    let vertices = [left, bottom, right, bottom, left, top, right, top];
-    let indices = [0u32, 1, 2, 2, 1, 3];
-
-    unsafe {
-        let vbo = gl.create_buffer().expect("Cannot create vertex buffer");
-        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
-        gl.buffer_data_u8_slice(
-            glow::ARRAY_BUFFER,
-            &vertices
-                .iter()
-                .flat_map(|f| f.to_ne_bytes())
-                .collect::<Vec<u8>>(),
-            glow::STATIC_DRAW,
-        );
-
-        let ibo = gl.create_buffer().expect("Cannot create index buffer");
-        gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ibo));
-        gl.buffer_data_u8_slice(
-            glow::ELEMENT_ARRAY_BUFFER,
-            &indices
-                .iter()
-                .flat_map(|i| i.to_ne_bytes())
-                .collect::<Vec<u8>>(),
-            glow::STATIC_DRAW,
-        );
-
-        gl.vertex_attrib_pointer_f32(pos_attrib, 2, glow::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(pos_attrib);
-        gl.uniform_4_f32(Some(color_location), color[0], color[1], color[2], color[3]);
-        gl.draw_elements(glow::TRIANGLES, 6, glow::UNSIGNED_INT, 0);
-
-        gl.delete_buffer(vbo);
-        gl.delete_buffer(ibo);
-    }
+/// Starts the game registered as `game_id` (see `game_ids`) in `container`, which the canvas
+/// fills and follows as it resizes, so the container needs a size of its own. "cannon-coop"
+/// is two-player co-op: arrow keys and Space for player one, A/D and W for player two.
+/// "breakout" is the classic paddle game on the same bricks, played with the mouse, a finger
+/// or the arrow keys.
+///
+/// `config` is an optional `GameConfig` object overriding some of the defaults. Fails with an
+/// `Error` carrying a `code` property if the game or config is unknown or invalid, or the
+/// browser can't run the game.
+#[wasm_bindgen]
+pub fn initialize(
+    container: Element,
+    game_id: &str,
+    config: JsValue,
+) -> Result<GameHandle, JsValue> {
+    let game = game::by_id(game_id).ok_or_else(|| InitError::UnknownGame(game_id.to_string()))?;
+    let config = parse_config(&config)?;
+    main_with_container(container, game, config).map_err(JsValue::from)
 }
 
-fn draw_circle(
-    gl: &glow::Context,
-    pos_attrib: u32,
-    color_location: &glow::UniformLocation,
-    cx: f32,
-    cy: f32,
-    radius: f32,
-    color: [f32; 4],
-    segments: u32,
-) {
-    let mut vertices = Vec::new();
-    vertices.push(cx); // Center
-    vertices.push(cy);
-    for i in 0..=segments {
-        let angle = 2.0 * std::f32::consts::PI * (i as f32) / (segments as f32);
-        let x = cx + radius * angle.cos();
-        let y = cy + radius * angle.sin();
-        vertices.push(x);
-        vertices.push(y);
-    }
-
-    let indices: Vec<u32> = (1..=segments).collect();
-    let indices = [0u32]
-        .iter()
-        .chain(&indices)
-        .chain(&[1])
-        .cloned()
-        .collect::<Vec<u32>>();
-
-    unsafe {
-        let vbo = gl.create_buffer().expect("Cannot create vertex buffer");
-        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
-        gl.buffer_data_u8_slice(
-            glow::ARRAY_BUFFER,
-            &vertices
-                .iter()
-                .flat_map(|f| f.to_ne_bytes())
-                .collect::<Vec<u8>>(),
-            glow::STATIC_DRAW,
-        );
-
-        let ibo = gl.create_buffer().expect("Cannot create index buffer");
-        gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ibo));
-        gl.buffer_data_u8_slice(
-            glow::ELEMENT_ARRAY_BUFFER,
-            &indices
-                .iter()
-                .flat_map(|i| i.to_ne_bytes())
-                .collect::<Vec<u8>>(),
-            glow::STATIC_DRAW,
-        );
-
-        gl.vertex_attrib_pointer_f32(pos_attrib, 2, glow::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(pos_attrib);
-        gl.uniform_4_f32(Some(color_location), color[0], color[1], color[2], color[3]);
-        gl.draw_elements(
-            glow::TRIANGLE_FAN,
-            indices.len() as i32,
-            glow::UNSIGNED_INT,
-            0,
-        );
-
-        gl.delete_buffer(vbo);
-        gl.delete_buffer(ibo);
-    }
+fn parse_config(config: &JsValue) -> Result<GameConfig, InitError> {
+    GameConfig::default()
+        .merged(config)
+        .map_err(InitError::Config)
 }
 
-fn create_translation_matrix(tx: f32, ty: f32) -> [f32; 16] {
-    [
-        1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, tx, ty, 0.0, 1.0,
-    ]
+/// The built-in tunables as a `GameConfig` object, e.g. to fill in a debug panel.
+#[wasm_bindgen]
+pub fn default_config() -> JsValue {
+    GameConfig::default().to_js()
 }
 
-fn create_model_matrix(tx: f32, ty: f32, theta: f32) -> [f32; 16] {
-    let cos_theta = theta.cos();
-    let sin_theta = theta.sin();
-    [
-        cos_theta, sin_theta, 0.0, 0.0, -sin_theta, cos_theta, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, tx,
-        ty, 0.0, 1.0,
-    ]
+/// Ids of the games `initialize` can start.
+#[wasm_bindgen]
+pub fn game_ids() -> js_sys::Array {
+    game::GAMES
+        .iter()
+        .map(|game| JsValue::from_str(game.id))
+        .collect()
 }
 
-fn draw_ground(gl: &glow::Context, pos_attrib: u32, color_location: &glow::UniformLocation) {
-    let vertices = [
-        -1.0,
-        -1.0, // Bottom-left
-        1.0,
-        -1.0, // Bottom-right
-        1.0,
-        GROUND_TOP + 0.02, // Top-right with slight variation
-        -1.0,
-        GROUND_TOP - 0.02, // Top-left with slight variation
-    ];
-    let indices = [0u32, 1, 2, 2, 3, 0];
-
-    unsafe {
-        let vbo = gl.create_buffer().expect("Cannot create vertex buffer");
-        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
-        gl.buffer_data_u8_slice(
-            glow::ARRAY_BUFFER,
-            &vertices
-                .iter()
-                .flat_map(|f| f.to_ne_bytes())
-                .collect::<Vec<u8>>(),
-            glow::STATIC_DRAW,
-        );
-
-        let ibo = gl.create_buffer().expect("Cannot create index buffer");
-        gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(ibo));
-        gl.buffer_data_u8_slice(
-            glow::ELEMENT_ARRAY_BUFFER,
-            &indices
-                .iter()
-                .flat_map(|i| i.to_ne_bytes())
-                .collect::<Vec<u8>>(),
-            glow::STATIC_DRAW,
-        );
-
-        gl.vertex_attrib_pointer_f32(pos_attrib, 2, glow::FLOAT, false, 0, 0);
-        gl.enable_vertex_attrib_array(pos_attrib);
-        gl.uniform_4_f32(
-            Some(color_location),
-            GROUND_COLOR[0],
-            GROUND_COLOR[1],
-            GROUND_COLOR[2],
-            GROUND_COLOR[3],
-        );
-        gl.draw_elements(glow::TRIANGLES, 6, glow::UNSIGNED_INT, 0);
-
-        gl.delete_buffer(vbo);
-        gl.delete_buffer(ibo);
-    }
+/// Names of the actions that keys can be bound to.
+#[wasm_bindgen]
+pub fn action_names() -> js_sys::Array {
+    Action::ALL
+        .iter()
+        .map(|action| JsValue::from_str(action.name()))
+        .collect()
 }
 
-// ### WebAssembly Bindings
-
+/// Names of the built-in themes, for building a theme picker.
 #[wasm_bindgen]
-pub fn initialize(container: Element) {
-    main_with_container(container);
+pub fn theme_names() -> js_sys::Array {
+    theme::THEMES
+        .iter()
+        .map(|theme| JsValue::from_str(theme.name))
+        .collect()
 }
 
 #[wasm_bindgen(start)]
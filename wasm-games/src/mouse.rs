@@ -0,0 +1,75 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, MouseEvent};
+
+// ### Pointer Lock
+
+/// Locks the pointer to the canvas for relative aiming.
+///
+/// winit doesn't support cursor grabbing on the web, so this uses the Pointer Lock API
+/// directly and sums the mouse movement reported while locked. Pressing Escape unlocks it.
+pub struct PointerLock {
+    canvas: HtmlCanvasElement,
+    movement: Rc<Cell<(f64, f64)>>, // CSS pixels moved since the last take, y down
+    on_move: Closure<dyn FnMut(MouseEvent)>,
+}
+
+impl PointerLock {
+    pub fn new(canvas: HtmlCanvasElement) -> Self {
+        let movement = Rc::new(Cell::new((0.0, 0.0)));
+        let sum = movement.clone();
+        let on_move = Closure::wrap(Box::new(move |event: MouseEvent| {
+            let (x, y) = sum.get();
+            sum.set((x + event.movement_x() as f64, y + event.movement_y() as f64));
+        }) as Box<dyn FnMut(MouseEvent)>);
+        let _ =
+            canvas.add_event_listener_with_callback("mousemove", on_move.as_ref().unchecked_ref());
+        PointerLock {
+            canvas,
+            movement,
+            on_move,
+        }
+    }
+
+    /// Asks the browser to lock the pointer. Only honoured from a click or key press.
+    pub fn request(&self) {
+        self.canvas.request_pointer_lock();
+    }
+
+    pub fn release(&self) {
+        if self.is_locked() {
+            if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+                document.exit_pointer_lock();
+            }
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.pointer_lock_element())
+            .is_some_and(|element| element == **self.canvas)
+    }
+
+    /// Movement in CSS pixels since the last call while the pointer was locked.
+    pub fn take_movement(&self) -> (f64, f64) {
+        let movement = self.movement.replace((0.0, 0.0));
+        if self.is_locked() {
+            movement
+        } else {
+            (0.0, 0.0)
+        }
+    }
+}
+
+impl Drop for PointerLock {
+    fn drop(&mut self) {
+        self.release();
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "mousemove",
+            self.on_move.as_ref().unchecked_ref(),
+        );
+    }
+}
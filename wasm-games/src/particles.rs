@@ -0,0 +1,170 @@
+use crate::atlas::Sprite;
+use crate::random::random;
+use crate::renderer::{BlendMode, Renderer};
+
+// ### Emitters
+
+/// How many particles an emitter releases.
+#[derive(Clone, Copy)]
+pub enum Emission {
+    /// This many particles at once.
+    Burst(u32),
+    /// This many particles per second for as long as the emitter is fed.
+    Continuous(f32),
+}
+
+/// Describes how particles are released and how they change over their life.
+///
+/// Angles are in radians from the positive x axis; colours and sizes are interpolated from
+/// their start to their end value over each particle's life.
+#[derive(Clone, Copy)]
+pub struct EmitterDef {
+    pub emission: Emission,
+    pub direction: f32, // Centre of the emission cone
+    pub spread: f32,    // Full width of the cone, 2π for every direction
+    pub speed: (f32, f32),
+    pub lifetime: (f32, f32), // Seconds
+    pub gravity: f32,         // Downward acceleration in units per second squared
+    pub drag: f32,            // Share of the velocity lost per second, exponentially
+    pub size: (f32, f32),     // Radius at birth and at death
+    pub color: ([f32; 4], [f32; 4]),
+    pub additive: bool, // Adds light instead of covering what is behind
+}
+
+impl EmitterDef {
+    /// The same emitter in `color`, fading out to transparent.
+    pub fn tinted(mut self, color: [f32; 4]) -> Self {
+        self.color = (color, [color[0], color[1], color[2], 0.0]);
+        self
+    }
+}
+
+// ### Particle System
+
+#[derive(Clone, Copy)]
+struct Particle {
+    x: f32,
+    y: f32,
+    dx: f32,
+    dy: f32,
+    age: f32,
+    lifetime: f32,
+    def: EmitterDef,
+}
+
+/// Every live particle, in storage allocated once up front.
+///
+/// Live particles are packed at the front of the pool; dead ones are swapped behind them and
+/// their slots reused. Once the cap is reached new particles are dropped.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    alive: usize,
+    capacity: usize,
+    speed_scale: f32, // Applied to every emitter's speeds
+}
+
+impl ParticleSystem {
+    pub fn new(capacity: usize) -> Self {
+        ParticleSystem {
+            particles: Vec::with_capacity(capacity),
+            alive: 0,
+            capacity,
+            speed_scale: 1.0,
+        }
+    }
+
+    /// Scales the speed of particles emitted from now on, as set by the config.
+    pub fn set_speed_scale(&mut self, scale: f32) {
+        self.speed_scale = scale;
+    }
+
+    /// Releases particles from `def` at (`x`, `y`). Continuous emitters release their share of
+    /// `delta_time`, rounded randomly so low rates still emit over several frames.
+    pub fn emit(&mut self, def: &EmitterDef, x: f32, y: f32, delta_time: f32) {
+        let count = match def.emission {
+            Emission::Burst(count) => count,
+            Emission::Continuous(rate) => (rate * delta_time + random() as f32) as u32,
+        };
+        for _ in 0..count {
+            let angle = def.direction + (random() as f32 - 0.5) * def.spread;
+            let speed = random_between(def.speed) * self.speed_scale;
+            self.spawn(Particle {
+                x,
+                y,
+                dx: speed * angle.cos(),
+                dy: speed * angle.sin(),
+                age: 0.0,
+                lifetime: random_between(def.lifetime),
+                def: *def,
+            });
+        }
+    }
+
+    fn spawn(&mut self, particle: Particle) {
+        if self.alive == self.capacity {
+            return;
+        }
+        if self.alive < self.particles.len() {
+            self.particles[self.alive] = particle;
+        } else {
+            self.particles.push(particle);
+        }
+        self.alive += 1;
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        let mut i = 0;
+        while i < self.alive {
+            let particle = &mut self.particles[i];
+            particle.age += delta_time;
+            if particle.age >= particle.lifetime {
+                self.alive -= 1;
+                self.particles.swap(i, self.alive);
+                continue;
+            }
+            let damping = (-particle.def.drag * delta_time).exp();
+            particle.dx *= damping;
+            particle.dy = particle.dy * damping - particle.def.gravity * delta_time;
+            particle.x += particle.dx * delta_time;
+            particle.y += particle.dy * delta_time;
+            i += 1;
+        }
+    }
+
+    /// Draws blended particles first, then additive ones on top.
+    pub fn draw(&self, renderer: &mut dyn Renderer) {
+        let live = &self.particles[..self.alive];
+        for &additive in [false, true].iter() {
+            if additive {
+                renderer.set_blend(BlendMode::Additive);
+            }
+            for particle in live.iter().filter(|p| p.def.additive == additive) {
+                let t = particle.age / particle.lifetime;
+                let radius = lerp(particle.def.size.0, particle.def.size.1, t);
+                let (start, end) = particle.def.color;
+                let mut color = [0.0; 4];
+                for channel in 0..4 {
+                    color[channel] = lerp(start[channel], end[channel], t);
+                }
+                // The bullet sprite doubles as a soft round particle
+                renderer.sprite(
+                    Sprite::Bullet,
+                    particle.x - radius,
+                    particle.y - radius,
+                    particle.x + radius,
+                    particle.y + radius,
+                    color,
+                );
+            }
+        }
+        renderer.set_blend(BlendMode::Alpha);
+    }
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+fn random_between((min, max): (f32, f32)) -> f32 {
+    min + random() as f32 * (max - min)
+}
@@ -0,0 +1,218 @@
+use crate::config::GameConfig;
+use crate::input::Action;
+use crate::upgrades::Progress;
+use crate::weapons::{HeatGauge, Magazine, SimpleCannon, Weapon};
+
+// ### Player
+
+pub const CANNON_WIDTH: f32 = 0.05;
+pub const CANNON_LENGTH: f32 = 0.1;
+pub const CANNON_Y: f32 = -0.9;
+pub const BASE_WIDTH: f32 = 0.1; // Cannon base width
+pub const BASE_HEIGHT: f32 = 0.05; // Cannon base height
+const CANNON_X_LIMIT: f32 = 1.0 - BASE_WIDTH / 2.0 - 0.01; // Walls, including the supports
+const HEAT_PER_SHOT: f32 = 0.012; // Heat added by every shot (gauge is 0..1)
+const HEAT_COOL_RATE: f32 = 0.35; // Heat dissipated per second
+const HEAT_RECOVER_LEVEL: f32 = 0.3; // Overheat lock releases below this level
+const MAGAZINE_SIZE: u32 = 150;
+const STARTING_RESERVE: u32 = 600; // Rounds carried besides the loaded magazine
+const RELOAD_TIME: f32 = 1.2; // Seconds to swap magazines
+const HEAVY_MAGAZINE_SIZE: u32 = 12;
+const HEAVY_STARTING_RESERVE: u32 = 36;
+const HEAVY_RELOAD_TIME: f32 = 2.0;
+
+/// One cannon with its own weapon, colour and score.
+pub struct Player {
+    pub cannon_x: f32,
+    pub cannon_vx: f32,
+    pub cannon_y: f32,
+    pub theta: f32,
+    pub min_x: f32, // Driving limits, a screen half each in co-op
+    pub max_x: f32,
+    pub left_pressed: bool,
+    pub right_pressed: bool,
+    pub fire_pressed: bool,
+    pub drive_left_pressed: bool,
+    pub drive_right_pressed: bool,
+    pub drive_axis: f32, // Analog throttle from -1.0 (left) to 1.0 (right)
+    pub aim_axis: f32,   // Analog aim from -1.0 (left) to 1.0 (right)
+    pub weapon: SimpleCannon,
+    pub holstered: Vec<SimpleCannon>, // Weapons not in hand, in switching order
+    pub touch_throttle: f32,          // Throttle from drive touches and the virtual joystick
+    pub touch_aiming: bool,           // A touch is aiming, so held keys don't turn the barrel
+    pub score: u32,
+    pub color: [f32; 4], // Barrel and bullet colour
+}
+
+impl Player {
+    /// Creates a player whose cannon starts at `cannon_x` and stays within `min_x..=max_x`.
+    /// Weapons only overheat or use ammunition if `config` turns those on.
+    pub fn new(
+        cannon_x: f32,
+        min_x: f32,
+        max_x: f32,
+        color: [f32; 4],
+        config: &GameConfig,
+    ) -> Self {
+        let mut weapon = SimpleCannon::new();
+        let mut heavy = SimpleCannon::heavy();
+        if config.weapon_heat {
+            weapon = weapon.with_heat(HeatGauge::new(
+                HEAT_PER_SHOT,
+                HEAT_COOL_RATE,
+                HEAT_RECOVER_LEVEL,
+            ));
+        }
+        if config.finite_ammo {
+            weapon =
+                weapon.with_magazine(Magazine::new(MAGAZINE_SIZE, STARTING_RESERVE, RELOAD_TIME));
+            heavy = heavy.with_magazine(Magazine::new(
+                HEAVY_MAGAZINE_SIZE,
+                HEAVY_STARTING_RESERVE,
+                HEAVY_RELOAD_TIME,
+            ));
+        }
+        Player {
+            cannon_x,
+            cannon_vx: 0.0,
+            cannon_y: CANNON_Y,
+            theta: 0.0,
+            min_x: min_x.max(-CANNON_X_LIMIT),
+            max_x: max_x.min(CANNON_X_LIMIT),
+            left_pressed: false,
+            right_pressed: false,
+            fire_pressed: false,
+            drive_left_pressed: false,
+            drive_right_pressed: false,
+            drive_axis: 0.0,
+            aim_axis: 0.0,
+            weapon,
+            holstered: vec![heavy],
+            touch_throttle: 0.0,
+            touch_aiming: false,
+            score: 0,
+            color,
+        }
+    }
+
+    /// Starts or stops `action`, returning whether it is one of the player's own actions.
+    pub fn set_action(&mut self, action: Action, pressed: bool) -> bool {
+        match action {
+            Action::AimLeft => self.left_pressed = pressed,
+            Action::AimRight => self.right_pressed = pressed,
+            Action::Fire => self.fire_pressed = pressed,
+            Action::DriveLeft => self.drive_left_pressed = pressed,
+            Action::DriveRight => self.drive_right_pressed = pressed,
+            Action::Reload if pressed => self.weapon.reload(),
+            Action::NextWeapon if pressed => self.switch_weapon(true),
+            Action::PreviousWeapon if pressed => self.switch_weapon(false),
+            Action::Reload | Action::NextWeapon | Action::PreviousWeapon => {}
+            _ => return false,
+        }
+        true
+    }
+
+    /// Rotates the barrel from held keys and the aim stick, unless a touch is aiming it.
+    pub fn rotate(&mut self, rotation_speed: f32, delta_time: f32) {
+        if !self.touch_aiming {
+            self.theta -= self.aim_axis * rotation_speed * delta_time;
+            if self.left_pressed {
+                self.theta += rotation_speed * delta_time;
+            }
+            if self.right_pressed {
+                self.theta -= rotation_speed * delta_time;
+            }
+        }
+        self.theta = self
+            .theta
+            .clamp(-std::f32::consts::PI / 2.0, std::f32::consts::PI / 2.0);
+    }
+
+    /// Puts the weapon in hand away and takes out the next one, or the previous one if not
+    /// `forward`.
+    pub fn switch_weapon(&mut self, forward: bool) {
+        if self.holstered.is_empty() {
+            return;
+        }
+        let next = if forward {
+            self.holstered.remove(0)
+        } else {
+            self.holstered.pop().unwrap()
+        };
+        let previous = std::mem::replace(&mut self.weapon, next);
+        if forward {
+            self.holstered.push(previous);
+        } else {
+            self.holstered.insert(0, previous);
+        }
+    }
+
+    pub fn apply_upgrades(&mut self, progress: &Progress) {
+        self.weapon.apply_upgrades(progress);
+        for weapon in &mut self.holstered {
+            weapon.apply_upgrades(progress);
+        }
+    }
+
+    /// Whether every weapon, holstered or not, has fired its last round.
+    pub fn is_out_of_ammo(&self) -> bool {
+        self.weapon.is_out_of_ammo() && self.holstered.iter().all(SimpleCannon::is_out_of_ammo)
+    }
+
+    /// Cools and reloads every weapon, including holstered ones.
+    pub fn update_weapons(&mut self, fire_rate_multiplier: f32, delta_time: f32) {
+        for weapon in std::iter::once(&mut self.weapon).chain(self.holstered.iter_mut()) {
+            weapon.set_fire_rate_multiplier(fire_rate_multiplier);
+            weapon.update(delta_time);
+        }
+    }
+
+    /// Drives the cannon along the ground from the keyboard, touches or the gamepad.
+    pub fn drive(&mut self, config: &GameConfig, delta_time: f32) {
+        let mut throttle = self.drive_axis + self.touch_throttle;
+        if self.drive_left_pressed {
+            throttle -= 1.0;
+        }
+        if self.drive_right_pressed {
+            throttle += 1.0;
+        }
+        let throttle = throttle.clamp(-1.0, 1.0);
+
+        // Brake when coasting or when steering against the current motion
+        if throttle == 0.0 || throttle * self.cannon_vx < 0.0 {
+            let braking = config.cannon_braking * delta_time;
+            if self.cannon_vx.abs() <= braking {
+                self.cannon_vx = 0.0;
+            } else {
+                self.cannon_vx -= braking * self.cannon_vx.signum();
+            }
+        }
+        self.cannon_vx = (self.cannon_vx + throttle * config.cannon_acceleration * delta_time)
+            .clamp(-config.cannon_max_speed, config.cannon_max_speed);
+        self.cannon_x += self.cannon_vx * delta_time;
+
+        // Stop dead at the walls
+        if self.cannon_x < self.min_x || self.cannon_x > self.max_x {
+            self.cannon_x = self.cannon_x.clamp(self.min_x, self.max_x);
+            self.cannon_vx = 0.0;
+        }
+    }
+
+    /// Points the barrel at a position in game coordinates, from wherever the cannon is.
+    pub fn aim_at(&mut self, x: f32, y: f32) {
+        let dx = x - self.cannon_x;
+        let dy = y - self.cannon_y;
+        // The barrel points up at theta = 0 and rotates counter-clockwise
+        self.theta = (-dx)
+            .atan2(dy)
+            .clamp(-std::f32::consts::PI / 2.0, std::f32::consts::PI / 2.0);
+    }
+}
+
+/// Position of the barrel tip for a cannon at (`cannon_x`, `cannon_y`) rotated by `theta`.
+pub fn barrel_tip(cannon_x: f32, cannon_y: f32, theta: f32) -> (f32, f32) {
+    (
+        cannon_x - CANNON_LENGTH * theta.sin(),
+        cannon_y + CANNON_LENGTH * theta.cos(),
+    )
+}
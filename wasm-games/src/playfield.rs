@@ -0,0 +1,231 @@
+use crate::atlas::Sprite;
+use crate::camera::Camera;
+use crate::config::GameConfig;
+use crate::particles::{Emission, EmitterDef};
+use crate::random::random;
+use crate::renderer::Renderer;
+use crate::text::{self, Align, TextStyle};
+use crate::theme::Theme;
+
+// ### Playfield
+
+const BALL_SIZE: f32 = 0.02;
+pub const BALL_RADIUS: f32 = BALL_SIZE / 2.0;
+pub const BRICK_ROWS: usize = 10;
+pub const BRICK_COLUMNS: usize = 10;
+pub const MAX_PARTICLES: usize = 2000; // Particles alive at once; more are dropped
+pub const GROUND_TOP: f32 = -0.95; // Top y-coordinate of ground
+pub const HUD_TEXT_SIZE: f32 = 0.035; // Height of HUD capitals in world units
+const MESSAGE_TEXT_SIZE: f32 = 0.08;
+pub const MESSAGE_DURATION: f32 = 2.0; // Seconds a message stays up, fading out over the last half
+pub const WORLD_WIDTH: f32 = 2.0; // Playfield size in world units, centred on the origin
+pub const WORLD_HEIGHT: f32 = 2.0;
+pub const SHAKE_PER_HIT: f32 = 0.004; // Trauma added by every brick hit (trauma is 0..1)
+pub const SHAKE_PER_BRICK: f32 = 0.08; // Trauma added by every destroyed brick
+pub const SHAKE_DECAY: f32 = 1.5; // Trauma lost per second
+pub const FLASH_PER_BRICK: f32 = 0.15; // Chromatic flash added by every destroyed brick
+pub const FLASH_DECAY: f32 = 4.0; // Flash lost per second
+
+pub const FULL_CIRCLE: f32 = 2.0 * std::f32::consts::PI;
+
+/// Sparks where a bullet strikes a brick, tinted with the brick colour.
+pub const HIT_SPARKS: EmitterDef = EmitterDef {
+    emission: Emission::Burst(3),
+    direction: 0.0,
+    spread: FULL_CIRCLE,
+    speed: (0.05, 0.25),
+    lifetime: (0.2, 0.4),
+    gravity: 0.5,
+    drag: 2.0,
+    size: (0.006, 0.002),
+    color: ([1.0; 4], [1.0, 1.0, 1.0, 0.0]),
+    additive: true,
+};
+
+/// Chunks of a destroyed brick falling away, tinted with the brick colour.
+pub const BRICK_DEBRIS: EmitterDef = EmitterDef {
+    emission: Emission::Burst(12),
+    direction: 0.0,
+    spread: FULL_CIRCLE,
+    speed: (0.1, 0.4),
+    lifetime: (0.4, 0.8),
+    gravity: 1.5,
+    drag: 1.0,
+    size: (0.012, 0.004),
+    color: ([1.0; 4], [1.0, 1.0, 1.0, 0.0]),
+    additive: false,
+};
+
+/// Represents a brick with position, health, and hit effect timer.
+pub struct Brick {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+    pub health: u32,
+    pub max_health: u32,
+    pub hit_timer: f32, // Timer for hit effect
+}
+
+impl Brick {
+    pub fn health_fraction(&self) -> f32 {
+        self.health as f32 / self.max_health as f32
+    }
+
+    /// Colour of the brick in `theme`, before any hit flash.
+    pub fn color(&self, theme: &Theme) -> [f32; 4] {
+        theme.brick_color(self.left, self.health_fraction())
+    }
+}
+
+/// HashGrid for efficient collision detection.
+pub struct HashGrid {
+    cell_width: f32,
+    cell_height: f32,
+    grid: Vec<Vec<usize>>,
+    grid_width: usize,
+    grid_height: usize,
+}
+
+impl HashGrid {
+    pub fn new(cell_width: f32, cell_height: f32, grid_width: usize, grid_height: usize) -> Self {
+        HashGrid {
+            cell_width,
+            cell_height,
+            grid: vec![vec![]; grid_width * grid_height],
+            grid_width,
+            grid_height,
+        }
+    }
+
+    pub fn get_cell_index(&self, x: f32, y: f32) -> (i32, i32) {
+        let i_x = ((x + 1.0) / self.cell_width).floor() as i32;
+        let i_y = ((y + 1.0) / self.cell_height).floor() as i32;
+        (i_x, i_y)
+    }
+
+    pub fn add_brick(&mut self, brick_index: usize, left: f32, bottom: f32, right: f32, top: f32) {
+        let (i_x_min, i_y_min) = self.get_cell_index(left, bottom);
+        let (i_x_max, i_y_max) = self.get_cell_index(right, top);
+        for i_x in i_x_min..=i_x_max {
+            for i_y in i_y_min..=i_y_max {
+                if i_x >= 0
+                    && i_x < self.grid_width as i32
+                    && i_y >= 0
+                    && i_y < self.grid_height as i32
+                {
+                    let index = (i_x as usize) * self.grid_height + (i_y as usize);
+                    self.grid[index].push(brick_index);
+                }
+            }
+        }
+    }
+
+    pub fn get_bricks_in_cell(&self, i_x: i32, i_y: i32) -> &[usize] {
+        if i_x >= 0 && i_x < self.grid_width as i32 && i_y >= 0 && i_y < self.grid_height as i32 {
+            let index = (i_x as usize) * self.grid_height + (i_y as usize);
+            &self.grid[index]
+        } else {
+            &[]
+        }
+    }
+}
+
+/// Lays out the bricks for `stage` and indexes them in a fresh hash grid.
+pub fn build_stage(stage: u32, config: &GameConfig) -> (Vec<Brick>, HashGrid) {
+    let health = config.brick_base_health.saturating_add(stage - 1);
+    let (width, height, spacing) = (
+        config.brick_width,
+        config.brick_height,
+        config.brick_spacing,
+    );
+    let mut bricks = Vec::new();
+    let span_x = (BRICK_COLUMNS - 1) as f32 * (width + spacing) + width;
+    let starting_x = -span_x / 2.0;
+    let starting_y = 1.0 - height - (BRICK_ROWS - 1) as f32 * (height + spacing);
+
+    for i in 0..BRICK_ROWS {
+        for j in 0..BRICK_COLUMNS {
+            let offset_x = (random() as f32 * 2.0 - 1.0) * config.brick_offset;
+            let offset_y = (random() as f32 * 2.0 - 1.0) * config.brick_offset;
+            let x = starting_x + (j as f32) * (width + spacing) + offset_x;
+            let y = starting_y + (i as f32) * (height + spacing) + offset_y;
+            bricks.push(Brick {
+                left: x,
+                bottom: y,
+                right: x + width,
+                top: y + height,
+                health,
+                max_health: health,
+                hit_timer: 0.0,
+            });
+        }
+    }
+
+    let grid_width = (2.0 / width).ceil() as usize;
+    let grid_height = (2.0 / height).ceil() as usize;
+    let mut hash_grid = HashGrid::new(width, height, grid_width, grid_height);
+
+    for (index, brick) in bricks.iter().enumerate() {
+        hash_grid.add_brick(index, brick.left, brick.bottom, brick.right, brick.top);
+    }
+
+    (bricks, hash_grid)
+}
+
+/// Draws the gradient sky over everything the camera shows and the ground below it.
+pub fn draw_playfield(renderer: &mut dyn Renderer, theme: &Theme, camera: &Camera) {
+    let (left, bottom, right, top) = camera.visible_bounds();
+    let [background_bottom, background_top] = theme.background;
+    renderer.gradient_rect(left, bottom, right, top, background_bottom, background_top);
+
+    // Draw ground, its top edge slightly sloped
+    renderer.quad(
+        [
+            (left, bottom),
+            (right, bottom),
+            (right, GROUND_TOP + 0.02),
+            (left, GROUND_TOP - 0.02),
+        ],
+        theme.ground,
+    );
+}
+
+/// Draws the bricks still standing, cracking as they lose health.
+pub fn draw_bricks(renderer: &mut dyn Renderer, bricks: &[Brick], theme: &Theme) {
+    for brick in bricks {
+        if brick.health > 0 {
+            let color = if brick.hit_timer > 0.0 {
+                theme.brick_hit
+            } else {
+                brick.color(theme)
+            };
+            let (left, bottom, right, top) = (brick.left, brick.bottom, brick.right, brick.top);
+            renderer.sprite(Sprite::Brick, left, bottom, right, top, color);
+            if let Some(cracks) = Sprite::cracks_for_health(brick.health_fraction()) {
+                renderer.sprite(cracks, left, bottom, right, top, [1.0; 4]);
+            }
+        }
+    }
+}
+
+/// Left, right and top of the part of the playfield on screen, which HUD text is kept inside.
+pub fn hud_bounds(camera: &Camera) -> (f32, f32, f32) {
+    let (left, _, right, top) = camera.visible_bounds();
+    (
+        left.max(-WORLD_WIDTH / 2.0),
+        right.min(WORLD_WIDTH / 2.0),
+        top.min(WORLD_HEIGHT / 2.0),
+    )
+}
+
+/// Draws `message` centred below the bricks, fading out over its last `remaining` seconds.
+pub fn draw_message(renderer: &mut dyn Renderer, message: &str, remaining: f32, theme: &Theme) {
+    let alpha = (remaining / (MESSAGE_DURATION / 2.0)).min(1.0);
+    let fade = |[r, g, b, a]: [f32; 4]| [r, g, b, a * alpha];
+    let shadow = TextStyle::new(MESSAGE_TEXT_SIZE, fade(theme.text_shadow)).aligned(Align::Center);
+    let style = TextStyle::new(MESSAGE_TEXT_SIZE, fade(theme.text)).aligned(Align::Center);
+    let offset = MESSAGE_TEXT_SIZE / 10.0;
+    text::draw_text(renderer, message, offset, -0.3 - offset, &shadow);
+    text::draw_text(renderer, message, 0.0, -0.3, &style);
+}
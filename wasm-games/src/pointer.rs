@@ -0,0 +1,106 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlCanvasElement, PointerEvent};
+use winit::event::TouchPhase;
+
+// ### Touch Pointers
+
+/// Pointer events listened to. The "over" and "out" events are only swallowed, so winit doesn't
+/// report a touch leaving as the cursor leaving.
+const POINTER_EVENTS: [&str; 6] = [
+    "pointerdown",
+    "pointermove",
+    "pointerup",
+    "pointercancel",
+    "pointerover",
+    "pointerout",
+];
+
+/// A touch on the canvas, positioned in CSS pixels from the canvas's top-left corner.
+pub struct TouchEvent {
+    pub id: u64,
+    pub phase: TouchPhase,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Collects touches on the canvas for the game loop.
+///
+/// winit's web backend reports touch pointers as the mouse and never emits touch events, so
+/// this listens to the Pointer Events API directly. Listeners are registered on the container in
+/// the capture phase, which runs before winit's own listeners on the canvas, and touch pointers
+/// are stopped there; mouse and pen pointers pass through to winit untouched.
+pub struct TouchPointers {
+    container: Element,
+    events: Rc<RefCell<Vec<TouchEvent>>>,
+    on_pointer: Closure<dyn FnMut(PointerEvent)>,
+}
+
+impl TouchPointers {
+    /// Starts listening for touches on `canvas` inside `container`. `on_gesture` is called
+    /// while handling each touch that starts or ends, when browsers allow audio to be unlocked.
+    pub fn new(
+        container: &Element,
+        canvas: HtmlCanvasElement,
+        on_gesture: impl Fn() + 'static,
+    ) -> Self {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let queue = events.clone();
+        let on_pointer = Closure::wrap(Box::new(move |event: PointerEvent| {
+            let on_canvas = event
+                .target()
+                .is_some_and(|target| &target == canvas.as_ref());
+            if !on_canvas || event.pointer_type() != "touch" {
+                return;
+            }
+            event.stop_propagation();
+            let phase = match event.type_().as_str() {
+                "pointerdown" => TouchPhase::Started,
+                "pointermove" => TouchPhase::Moved,
+                "pointerup" => TouchPhase::Ended,
+                "pointercancel" => TouchPhase::Cancelled,
+                _ => return,
+            };
+            if phase != TouchPhase::Moved {
+                on_gesture();
+            }
+            queue.borrow_mut().push(TouchEvent {
+                id: event.pointer_id() as u64,
+                phase,
+                x: event.offset_x() as f64,
+                y: event.offset_y() as f64,
+            });
+        }) as Box<dyn FnMut(PointerEvent)>);
+        for &name in POINTER_EVENTS.iter() {
+            let _ = container.add_event_listener_with_callback_and_bool(
+                name,
+                on_pointer.as_ref().unchecked_ref(),
+                true,
+            );
+        }
+        TouchPointers {
+            container: container.clone(),
+            events,
+            on_pointer,
+        }
+    }
+
+    /// Touches since the last call, oldest first.
+    pub fn take(&self) -> Vec<TouchEvent> {
+        self.events.replace(Vec::new())
+    }
+}
+
+impl Drop for TouchPointers {
+    fn drop(&mut self) {
+        for &name in POINTER_EVENTS.iter() {
+            let _ = self.container.remove_event_listener_with_callback_and_bool(
+                name,
+                self.on_pointer.as_ref().unchecked_ref(),
+                true,
+            );
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::random::random;
+
+// ### Power-up Definitions
+
+/// Timed effects granted by power-ups dropped from destroyed bricks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Splits bullets in flight and makes every shot fire three bullets.
+    Multiball,
+    /// Shortens the weapon cooldown; each stack adds another multiple of the base rate.
+    RapidFire,
+    /// Bullets pass through several bricks instead of bouncing off the first.
+    Piercing,
+    /// Bullets bounce off the ground instead of being lost.
+    Shield,
+    /// Slows down everything except the cannon.
+    SlowMotion,
+}
+
+/// What collecting a power-up does while the same kind is already active.
+#[derive(Clone, Copy, Debug)]
+pub enum StackPolicy {
+    /// Restart the timer at the full duration.
+    Refresh,
+    /// Add the full duration to the remaining time, up to `max_duration`.
+    Extend { max_duration: f32 },
+    /// Add a stack, up to `max_stacks`, and restart the timer.
+    Intensify { max_stacks: u32 },
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::Multiball,
+        PowerUpKind::RapidFire,
+        PowerUpKind::Piercing,
+        PowerUpKind::Shield,
+        PowerUpKind::SlowMotion,
+    ];
+
+    /// Picks a power-up uniformly at random.
+    pub fn random() -> Self {
+        let index = (random() * Self::ALL.len() as f64) as usize;
+        Self::ALL[index.min(Self::ALL.len() - 1)]
+    }
+
+    /// Duration in seconds of a single pickup.
+    pub fn duration(self) -> f32 {
+        match self {
+            PowerUpKind::Multiball => 6.0,
+            PowerUpKind::RapidFire => 8.0,
+            PowerUpKind::Piercing => 8.0,
+            PowerUpKind::Shield => 10.0,
+            PowerUpKind::SlowMotion => 5.0,
+        }
+    }
+
+    pub fn stack_policy(self) -> StackPolicy {
+        match self {
+            PowerUpKind::Multiball => StackPolicy::Refresh,
+            PowerUpKind::RapidFire => StackPolicy::Intensify { max_stacks: 3 },
+            PowerUpKind::Piercing => StackPolicy::Refresh,
+            PowerUpKind::Shield => StackPolicy::Extend { max_duration: 30.0 },
+            PowerUpKind::SlowMotion => StackPolicy::Refresh,
+        }
+    }
+
+    /// Name shown when the power-up is collected.
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUpKind::Multiball => "Multiball",
+            PowerUpKind::RapidFire => "Rapid fire",
+            PowerUpKind::Piercing => "Piercing",
+            PowerUpKind::Shield => "Shield",
+            PowerUpKind::SlowMotion => "Slow motion",
+        }
+    }
+
+    /// Color of the falling pickup and of its HUD timer.
+    pub fn color(self) -> [f32; 4] {
+        match self {
+            PowerUpKind::Multiball => [0.2, 0.9, 1.0, 1.0], // Cyan
+            PowerUpKind::RapidFire => [1.0, 0.4, 0.1, 1.0], // Orange
+            PowerUpKind::Piercing => [0.9, 0.2, 0.9, 1.0],  // Magenta
+            PowerUpKind::Shield => [0.3, 0.5, 1.0, 1.0],    // Blue
+            PowerUpKind::SlowMotion => [0.7, 1.0, 0.3, 1.0], // Lime
+        }
+    }
+}
+
+// ### Active Power-ups
+
+/// A power-up currently in effect.
+pub struct ActivePowerUp {
+    pub kind: PowerUpKind,
+    pub remaining: f32,
+    pub duration: f32, // Full length of the current timer, for the HUD
+    pub stacks: u32,
+}
+
+impl ActivePowerUp {
+    /// Remaining time as a fraction of the current timer, from 1.0 down to 0.0.
+    pub fn fraction_remaining(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.remaining / self.duration).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The set of active power-ups, at most one entry per kind.
+#[derive(Default)]
+pub struct PowerUps {
+    active: Vec<ActivePowerUp>,
+}
+
+impl PowerUps {
+    /// Activates `kind`, applying its stacking policy if it is already active.
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        let duration = kind.duration();
+        let existing = match self.active.iter_mut().find(|active| active.kind == kind) {
+            Some(existing) => existing,
+            None => {
+                self.active.push(ActivePowerUp {
+                    kind,
+                    remaining: duration,
+                    duration,
+                    stacks: 1,
+                });
+                return;
+            }
+        };
+
+        match kind.stack_policy() {
+            StackPolicy::Refresh => {
+                existing.remaining = duration;
+                existing.duration = duration;
+            }
+            StackPolicy::Extend { max_duration } => {
+                existing.remaining = (existing.remaining + duration).min(max_duration);
+                existing.duration = existing.duration.max(existing.remaining);
+            }
+            StackPolicy::Intensify { max_stacks } => {
+                existing.stacks = (existing.stacks + 1).min(max_stacks);
+                existing.remaining = duration;
+                existing.duration = duration;
+            }
+        }
+    }
+
+    /// Counts down all timers and drops expired power-ups.
+    pub fn update(&mut self, delta_time: f32) {
+        for active in &mut self.active {
+            active.remaining -= delta_time;
+        }
+        self.active.retain(|active| active.remaining > 0.0);
+    }
+
+    /// Number of stacks of `kind`, or 0 if it is not active.
+    pub fn stacks(&self, kind: PowerUpKind) -> u32 {
+        self.active
+            .iter()
+            .find(|active| active.kind == kind)
+            .map_or(0, |active| active.stacks)
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.stacks(kind) > 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActivePowerUp> {
+        self.active.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remaining(power_ups: &PowerUps, kind: PowerUpKind) -> f32 {
+        power_ups
+            .iter()
+            .find(|active| active.kind == kind)
+            .map_or(0.0, |active| active.remaining)
+    }
+
+    #[test]
+    fn refresh_restarts_the_timer() {
+        let mut power_ups = PowerUps::default();
+        power_ups.activate(PowerUpKind::Piercing);
+        power_ups.update(5.0);
+        assert_eq!(remaining(&power_ups, PowerUpKind::Piercing), 3.0);
+        power_ups.activate(PowerUpKind::Piercing);
+        assert_eq!(remaining(&power_ups, PowerUpKind::Piercing), 8.0);
+        assert_eq!(power_ups.stacks(PowerUpKind::Piercing), 1);
+    }
+
+    #[test]
+    fn extend_adds_time_up_to_the_limit() {
+        let mut power_ups = PowerUps::default();
+        power_ups.activate(PowerUpKind::Shield);
+        power_ups.update(4.0);
+        power_ups.activate(PowerUpKind::Shield);
+        assert_eq!(remaining(&power_ups, PowerUpKind::Shield), 16.0);
+        for _ in 0..3 {
+            power_ups.activate(PowerUpKind::Shield);
+        }
+        assert_eq!(remaining(&power_ups, PowerUpKind::Shield), 30.0);
+        // The HUD timer stretches to the longest time left
+        let shield = power_ups.iter().next().unwrap();
+        assert_eq!(shield.fraction_remaining(), 1.0);
+    }
+
+    #[test]
+    fn intensify_adds_stacks_up_to_the_limit() {
+        let mut power_ups = PowerUps::default();
+        for _ in 0..5 {
+            power_ups.activate(PowerUpKind::RapidFire);
+        }
+        assert_eq!(power_ups.stacks(PowerUpKind::RapidFire), 3);
+        power_ups.update(6.0);
+        power_ups.activate(PowerUpKind::RapidFire);
+        assert_eq!(remaining(&power_ups, PowerUpKind::RapidFire), 8.0);
+    }
+
+    #[test]
+    fn one_entry_per_kind() {
+        let mut power_ups = PowerUps::default();
+        for _ in 0..2 {
+            for kind in PowerUpKind::ALL.iter().copied() {
+                power_ups.activate(kind);
+            }
+        }
+        assert_eq!(power_ups.iter().count(), PowerUpKind::ALL.len());
+    }
+
+    #[test]
+    fn power_ups_expire_on_their_own_timers() {
+        let mut power_ups = PowerUps::default();
+        power_ups.activate(PowerUpKind::SlowMotion); // 5 seconds
+        power_ups.activate(PowerUpKind::Multiball); // 6 seconds
+        power_ups.update(5.0);
+        assert!(!power_ups.is_active(PowerUpKind::SlowMotion));
+        assert!(power_ups.is_active(PowerUpKind::Multiball));
+        assert_eq!(power_ups.stacks(PowerUpKind::SlowMotion), 0);
+        power_ups.update(1.0);
+        assert_eq!(power_ups.iter().count(), 0);
+    }
+}
@@ -0,0 +1,23 @@
+// ### Random Numbers
+
+/// A uniformly distributed number in `0.0..1.0` from the browser's generator.
+#[cfg(target_arch = "wasm32")]
+pub fn random() -> f64 {
+    js_sys::Math::random()
+}
+
+/// Host builds have no browser to ask, so tests get the same xorshift sequence on every run.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn random() -> f64 {
+    use std::cell::Cell;
+    thread_local!(static STATE: Cell<u64> = const { Cell::new(0x2545_f491_4f6c_dd1d) });
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        // The top 53 bits fill an f64 mantissa
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
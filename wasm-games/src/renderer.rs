@@ -0,0 +1,251 @@
+use crate::atlas::{Sprite, WHITE_UV};
+use crate::camera::Camera;
+
+mod post;
+mod software;
+mod webgl;
+
+pub use software::SoftwareRenderer;
+pub use webgl::WebGlRenderer;
+
+// ### Renderer Trait
+
+/// A vertex in world coordinates with an atlas texture coordinate and its own colour.
+///
+/// The colour multiplies the sampled texel, so shapes without a sprite sample the atlas's
+/// white cell and keep their flat colour.
+#[derive(Clone, Copy)]
+pub struct Vertex {
+    pub x: f32,
+    pub y: f32,
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl Vertex {
+    pub fn flat(x: f32, y: f32, color: [f32; 4]) -> Self {
+        Vertex {
+            x,
+            y,
+            uv: WHITE_UV,
+            color,
+        }
+    }
+}
+
+/// How shapes are combined with what is already drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Covers what is behind in proportion to alpha.
+    Alpha,
+    /// Adds colour scaled by alpha, brightening what is behind.
+    Additive,
+}
+
+/// Full-screen filter applied over the finished frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenFilter {
+    None,
+    /// Darkens the corners.
+    Vignette,
+    /// Vignette with scanlines and a slightly curved screen.
+    Crt,
+}
+
+impl ScreenFilter {
+    /// The filter after this one, wrapping around, for cycling through them from a key.
+    pub fn next(self) -> Self {
+        match self {
+            ScreenFilter::None => ScreenFilter::Vignette,
+            ScreenFilter::Vignette => ScreenFilter::Crt,
+            ScreenFilter::Crt => ScreenFilter::None,
+        }
+    }
+}
+
+/// Screen-space effects applied when a frame is presented.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PostEffects {
+    pub bloom: f32, // Strength of the glow around shapes drawn with `set_glow`
+    pub filter: ScreenFilter,
+    pub chromatic: f32, // Colour fringing from 0.0 to 1.0, for impact flashes
+}
+
+impl Default for PostEffects {
+    fn default() -> Self {
+        PostEffects {
+            bloom: 0.0,
+            filter: ScreenFilter::None,
+            chromatic: 0.0,
+        }
+    }
+}
+
+/// A drawing backend.
+///
+/// Coordinates are world units with y pointing up, projected by the last camera given to
+/// `set_camera`. Shapes are alpha blended in the order they are submitted and every shape is
+/// built from `triangle`, so a backend only has to rasterise triangles.
+pub trait Renderer {
+    /// Sets the projection and viewport, resizing the drawing surface to the camera's screen.
+    fn set_camera(&mut self, camera: &Camera);
+
+    /// Fills the whole surface with `color`, discarding anything drawn before.
+    fn clear(&mut self, color: [f32; 4]);
+
+    fn triangle(&mut self, a: Vertex, b: Vertex, c: Vertex);
+
+    /// Finishes the frame, drawing anything the backend still has queued.
+    fn present(&mut self);
+
+    /// Sets how shapes submitted from now on are blended. Frames start with `BlendMode::Alpha`.
+    fn set_blend(&mut self, blend: BlendMode);
+
+    /// Sets how strongly shapes submitted from now on bloom, from 0.0 for none. Backends
+    /// without post-processing ignore it.
+    fn set_glow(&mut self, _glow: f32) {}
+
+    /// Sets the effects applied when frames are presented. Backends without post-processing
+    /// ignore them.
+    fn set_effects(&mut self, _effects: PostEffects) {}
+
+    /// Quad from four corners given counter-clockwise, each with a texture coordinate and colour.
+    fn textured_quad(
+        &mut self,
+        corners: [(f32, f32); 4],
+        uvs: [[f32; 2]; 4],
+        colors: [[f32; 4]; 4],
+    ) {
+        let vertex = |i: usize| Vertex {
+            x: corners[i].0,
+            y: corners[i].1,
+            uv: uvs[i],
+            color: colors[i],
+        };
+        self.triangle(vertex(0), vertex(1), vertex(2));
+        self.triangle(vertex(2), vertex(3), vertex(0));
+    }
+
+    /// Quad from four corners given counter-clockwise, each with its own colour.
+    fn quad_colored(&mut self, corners: [(f32, f32); 4], colors: [[f32; 4]; 4]) {
+        self.textured_quad(corners, [WHITE_UV; 4], colors);
+    }
+
+    fn quad(&mut self, corners: [(f32, f32); 4], color: [f32; 4]) {
+        self.quad_colored(corners, [color; 4]);
+    }
+
+    fn rect(&mut self, left: f32, bottom: f32, right: f32, top: f32, color: [f32; 4]) {
+        self.quad(
+            [(left, bottom), (right, bottom), (right, top), (left, top)],
+            color,
+        );
+    }
+
+    /// Rectangle blending vertically from `bottom_color` to `top_color`.
+    fn gradient_rect(
+        &mut self,
+        left: f32,
+        bottom: f32,
+        right: f32,
+        top: f32,
+        bottom_color: [f32; 4],
+        top_color: [f32; 4],
+    ) {
+        self.quad_colored(
+            [(left, bottom), (right, bottom), (right, top), (left, top)],
+            [bottom_color, bottom_color, top_color, top_color],
+        );
+    }
+
+    /// Rectangle given in local coordinates, rotated by `theta` and moved to (`x`, `y`).
+    fn rotated_rect(
+        &mut self,
+        x: f32,
+        y: f32,
+        theta: f32,
+        local: (f32, f32, f32, f32),
+        color: [f32; 4],
+    ) {
+        self.quad(rotate_rect(x, y, theta, local), color);
+    }
+
+    /// Atlas sprite stretched over a rectangle and tinted by `color`.
+    fn sprite(
+        &mut self,
+        sprite: Sprite,
+        left: f32,
+        bottom: f32,
+        right: f32,
+        top: f32,
+        color: [f32; 4],
+    ) {
+        self.textured_quad(
+            [(left, bottom), (right, bottom), (right, top), (left, top)],
+            sprite_uvs(sprite),
+            [color; 4],
+        );
+    }
+
+    /// Atlas sprite over a rectangle in local coordinates, rotated by `theta` and moved to
+    /// (`x`, `y`).
+    fn rotated_sprite(
+        &mut self,
+        sprite: Sprite,
+        x: f32,
+        y: f32,
+        theta: f32,
+        local: (f32, f32, f32, f32),
+        color: [f32; 4],
+    ) {
+        self.textured_quad(
+            rotate_rect(x, y, theta, local),
+            sprite_uvs(sprite),
+            [color; 4],
+        );
+    }
+
+    fn circle(&mut self, cx: f32, cy: f32, radius: f32, color: [f32; 4], segments: u32) {
+        let center = Vertex::flat(cx, cy, color);
+        let point = |i: u32| {
+            let angle = 2.0 * std::f32::consts::PI * (i as f32) / (segments as f32);
+            Vertex::flat(cx + radius * angle.cos(), cy + radius * angle.sin(), color)
+        };
+        for i in 0..segments {
+            self.triangle(center, point(i), point(i + 1));
+        }
+    }
+}
+
+/// Corners of a rectangle given in local coordinates, rotated by `theta` and moved to (`x`, `y`).
+fn rotate_rect(
+    x: f32,
+    y: f32,
+    theta: f32,
+    (left, bottom, right, top): (f32, f32, f32, f32),
+) -> [(f32, f32); 4] {
+    let (sin, cos) = theta.sin_cos();
+    let transform = |(local_x, local_y): (f32, f32)| {
+        (
+            x + local_x * cos - local_y * sin,
+            y + local_x * sin + local_y * cos,
+        )
+    };
+    [
+        transform((left, bottom)),
+        transform((right, bottom)),
+        transform((right, top)),
+        transform((left, top)),
+    ]
+}
+
+/// Texture coordinates for the corners of a quad, bottom-left first and counter-clockwise.
+fn sprite_uvs(sprite: Sprite) -> [[f32; 2]; 4] {
+    let region = sprite.region();
+    [
+        [region.left, region.bottom],
+        [region.right, region.bottom],
+        [region.right, region.top],
+        [region.left, region.top],
+    ]
+}
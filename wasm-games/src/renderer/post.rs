@@ -0,0 +1,320 @@
+use glow::HasContext;
+
+use super::webgl::compile_program;
+use super::{PostEffects, ScreenFilter};
+
+// ### Post-processing
+
+/// Covers the screen with one triangle, without any vertex buffers.
+const FULLSCREEN_VERTEX_SHADER_SOURCE: &str = "#version 300 es
+out vec2 v_uv;
+void main() {
+    vec2 corner = vec2(float((gl_VertexID << 1) & 2), float(gl_VertexID & 2));
+    v_uv = corner;
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+/// Nine-tap Gaussian blur along `direction`, given in texels of `source`.
+const BLUR_FRAGMENT_SHADER_SOURCE: &str = "#version 300 es
+precision mediump float;
+in vec2 v_uv;
+uniform sampler2D source;
+uniform vec2 direction;
+out vec4 fragColor;
+void main() {
+    float weights[5] = float[](0.227, 0.194, 0.122, 0.054, 0.016);
+    vec3 color = texture(source, v_uv).rgb * weights[0];
+    for (int i = 1; i < 5; i++) {
+        vec2 offset = direction * float(i);
+        color += texture(source, v_uv + offset).rgb * weights[i];
+        color += texture(source, v_uv - offset).rgb * weights[i];
+    }
+    fragColor = vec4(color, 1.0);
+}
+";
+
+/// Adds bloom to the scene and applies chromatic aberration and the screen filter
+/// (0 none, 1 vignette, 2 CRT).
+const COMPOSITE_FRAGMENT_SHADER_SOURCE: &str = "#version 300 es
+precision mediump float;
+in vec2 v_uv;
+uniform sampler2D scene;
+uniform sampler2D bloom;
+uniform float bloom_strength;
+uniform float chromatic;
+uniform int filter_mode;
+uniform float screen_height;
+out vec4 fragColor;
+void main() {
+    vec2 uv = v_uv;
+    if (filter_mode == 2) {
+        // Bulge like a curved tube and black out what falls off the edge
+        vec2 centered = uv * 2.0 - 1.0;
+        centered *= 1.0 + 0.04 * dot(centered.yx, centered.yx);
+        uv = centered * 0.5 + 0.5;
+        if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+            fragColor = vec4(0.0, 0.0, 0.0, 1.0);
+            return;
+        }
+    }
+    vec2 offset = (uv - 0.5) * chromatic * 0.02;
+    vec3 color = vec3(
+        texture(scene, uv + offset).r,
+        texture(scene, uv).g,
+        texture(scene, uv - offset).b
+    );
+    color += texture(bloom, uv).rgb * bloom_strength;
+    if (filter_mode >= 1) {
+        color *= smoothstep(0.85, 0.35, distance(uv, vec2(0.5)));
+    }
+    if (filter_mode == 2) {
+        color *= 0.85 + 0.15 * sin(uv.y * screen_height * 3.14159);
+    }
+    fragColor = vec4(color, 1.0);
+}
+";
+
+/// Colour texture with a framebuffer rendering into it.
+struct Target {
+    texture: glow::Texture,
+    framebuffer: glow::Framebuffer,
+}
+
+/// Off-screen pass chain: the scene is drawn into a colour and a glow attachment, the glow is
+/// blurred at half resolution and both are composited onto the canvas.
+pub struct PostProcessor {
+    scene: Target,
+    glow_texture: glow::Texture, // Second attachment of the scene framebuffer
+    blur_targets: [Target; 2],
+    blur_program: glow::Program,
+    composite_program: glow::Program,
+    empty_vertex_array: glow::VertexArray,
+    width: i32,
+    height: i32,
+}
+
+impl PostProcessor {
+    /// Creates the pass chain for a canvas of `width` by `height` pixels, failing if the
+    /// device can't render to the textures.
+    pub fn new(gl: &glow::Context, width: i32, height: i32) -> Result<Self, String> {
+        unsafe {
+            let blur_program = compile_program(
+                gl,
+                FULLSCREEN_VERTEX_SHADER_SOURCE,
+                BLUR_FRAGMENT_SHADER_SOURCE,
+            )?;
+            let composite_program = compile_program(
+                gl,
+                FULLSCREEN_VERTEX_SHADER_SOURCE,
+                COMPOSITE_FRAGMENT_SHADER_SOURCE,
+            )?;
+            gl.use_program(Some(composite_program));
+            gl.uniform_1_i32(
+                gl.get_uniform_location(composite_program, "scene").as_ref(),
+                0,
+            );
+            gl.uniform_1_i32(
+                gl.get_uniform_location(composite_program, "bloom").as_ref(),
+                1,
+            );
+            let empty_vertex_array = gl.create_vertex_array()?;
+
+            let (scene, glow_texture) = create_scene_target(gl, width, height)?;
+            let blur_targets = [
+                create_target(gl, width / 2, height / 2)?,
+                create_target(gl, width / 2, height / 2)?,
+            ];
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+
+            Ok(PostProcessor {
+                scene,
+                glow_texture,
+                blur_targets,
+                blur_program,
+                composite_program,
+                empty_vertex_array,
+                width,
+                height,
+            })
+        }
+    }
+
+    /// Recreates the textures if the canvas size changed.
+    pub fn resize(&mut self, gl: &glow::Context, width: i32, height: i32) -> Result<(), String> {
+        if (width, height) == (self.width, self.height) {
+            return Ok(());
+        }
+        unsafe {
+            self.delete_targets(gl);
+            let (scene, glow_texture) = create_scene_target(gl, width, height)?;
+            self.scene = scene;
+            self.glow_texture = glow_texture;
+            self.blur_targets = [
+                create_target(gl, width / 2, height / 2)?,
+                create_target(gl, width / 2, height / 2)?,
+            ];
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+        }
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Redirects drawing into the scene and clears it.
+    pub fn begin(&self, gl: &glow::Context, mut color: [f32; 4]) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.scene.framebuffer));
+            gl.draw_buffers(&[glow::COLOR_ATTACHMENT0, glow::COLOR_ATTACHMENT1]);
+            gl.clear_buffer_f32_slice(glow::COLOR, 0, &mut color);
+            gl.clear_buffer_f32_slice(glow::COLOR, 1, &mut [0.0, 0.0, 0.0, 0.0]);
+        }
+    }
+
+    /// Blurs the glow and composites everything onto the canvas.
+    pub fn finish(&self, gl: &glow::Context, effects: &PostEffects) {
+        unsafe {
+            gl.disable(glow::BLEND);
+            gl.bind_vertex_array(Some(self.empty_vertex_array));
+            gl.active_texture(glow::TEXTURE0);
+
+            // Blur the glow horizontally, then vertically
+            gl.use_program(Some(self.blur_program));
+            let direction = gl.get_uniform_location(self.blur_program, "direction");
+            gl.viewport(0, 0, self.width / 2, self.height / 2);
+            let passes = [
+                (self.glow_texture, 0, (2.0 / self.width as f32, 0.0)),
+                (
+                    self.blur_targets[0].texture,
+                    1,
+                    (0.0, 2.0 / self.height as f32),
+                ),
+            ];
+            for &(source, target, (x, y)) in passes.iter() {
+                gl.bind_framebuffer(
+                    glow::FRAMEBUFFER,
+                    Some(self.blur_targets[target].framebuffer),
+                );
+                gl.bind_texture(glow::TEXTURE_2D, Some(source));
+                gl.uniform_2_f32(direction.as_ref(), x, y);
+                gl.draw_arrays(glow::TRIANGLES, 0, 3);
+            }
+
+            // Composite onto the canvas
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            gl.viewport(0, 0, self.width, self.height);
+            gl.use_program(Some(self.composite_program));
+            let uniform = |name: &str| gl.get_uniform_location(self.composite_program, name);
+            gl.uniform_1_f32(uniform("bloom_strength").as_ref(), effects.bloom);
+            gl.uniform_1_f32(uniform("chromatic").as_ref(), effects.chromatic);
+            let filter_mode = match effects.filter {
+                ScreenFilter::None => 0,
+                ScreenFilter::Vignette => 1,
+                ScreenFilter::Crt => 2,
+            };
+            gl.uniform_1_i32(uniform("filter_mode").as_ref(), filter_mode);
+            gl.uniform_1_f32(uniform("screen_height").as_ref(), self.height as f32);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.scene.texture));
+            gl.active_texture(glow::TEXTURE1);
+            gl.bind_texture(glow::TEXTURE_2D, Some(self.blur_targets[1].texture));
+            gl.draw_arrays(glow::TRIANGLES, 0, 3);
+
+            gl.active_texture(glow::TEXTURE0);
+            gl.enable(glow::BLEND);
+        }
+    }
+
+    /// Releases every GL object. The processor must not be used afterwards.
+    pub fn delete(&self, gl: &glow::Context) {
+        unsafe {
+            self.delete_targets(gl);
+            gl.delete_program(self.blur_program);
+            gl.delete_program(self.composite_program);
+            gl.delete_vertex_array(self.empty_vertex_array);
+        }
+    }
+
+    unsafe fn delete_targets(&self, gl: &glow::Context) {
+        for target in std::iter::once(&self.scene).chain(self.blur_targets.iter()) {
+            gl.delete_framebuffer(target.framebuffer);
+            gl.delete_texture(target.texture);
+        }
+        gl.delete_texture(self.glow_texture);
+    }
+}
+
+unsafe fn create_texture(
+    gl: &glow::Context,
+    width: i32,
+    height: i32,
+) -> Result<glow::Texture, String> {
+    let texture = gl.create_texture()?;
+    gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    gl.tex_image_2d(
+        glow::TEXTURE_2D,
+        0,
+        glow::RGBA as i32,
+        width.max(1),
+        height.max(1),
+        0,
+        glow::RGBA,
+        glow::UNSIGNED_BYTE,
+        None,
+    );
+    let parameters = [
+        (glow::TEXTURE_MIN_FILTER, glow::LINEAR),
+        (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+        (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+        (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+    ];
+    for &(parameter, value) in parameters.iter() {
+        gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+    }
+    Ok(texture)
+}
+
+unsafe fn check_framebuffer(gl: &glow::Context) -> Result<(), String> {
+    let status = gl.check_framebuffer_status(glow::FRAMEBUFFER);
+    if status == glow::FRAMEBUFFER_COMPLETE {
+        Ok(())
+    } else {
+        Err(format!("Framebuffer incomplete: 0x{:x}", status))
+    }
+}
+
+unsafe fn create_target(gl: &glow::Context, width: i32, height: i32) -> Result<Target, String> {
+    let texture = create_texture(gl, width, height)?;
+    let framebuffer = gl.create_framebuffer()?;
+    gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+    gl.framebuffer_texture_2d(
+        glow::FRAMEBUFFER,
+        glow::COLOR_ATTACHMENT0,
+        glow::TEXTURE_2D,
+        Some(texture),
+        0,
+    );
+    check_framebuffer(gl)?;
+    Ok(Target {
+        texture,
+        framebuffer,
+    })
+}
+
+/// Scene framebuffer with the colour in attachment 0 and the glow in attachment 1.
+unsafe fn create_scene_target(
+    gl: &glow::Context,
+    width: i32,
+    height: i32,
+) -> Result<(Target, glow::Texture), String> {
+    let scene = create_target(gl, width, height)?;
+    let glow_texture = create_texture(gl, width, height)?;
+    gl.framebuffer_texture_2d(
+        glow::FRAMEBUFFER,
+        glow::COLOR_ATTACHMENT1,
+        glow::TEXTURE_2D,
+        Some(glow_texture),
+        0,
+    );
+    check_framebuffer(gl)?;
+    Ok((scene, glow_texture))
+}
@@ -0,0 +1,369 @@
+use super::{BlendMode, Renderer, Vertex};
+use crate::atlas::{self, ATLAS_HEIGHT, ATLAS_WIDTH};
+use crate::camera::Camera;
+
+// ### Software Backend
+
+/// Rasterises on the CPU into an in-memory RGBA buffer, for rendering frames without a GPU.
+///
+/// Pixels are sampled at their centres and blended like the WebGL backend
+/// (`SRC_ALPHA, ONE_MINUS_SRC_ALPHA`), so output is deterministic and can be compared against
+/// stored golden images. Textures come from the built-in atlas, sampled at the nearest texel.
+/// Rows are stored top to bottom.
+pub struct SoftwareRenderer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>, // RGBA, 4 bytes per pixel
+    projection: [f32; 16],
+    viewport: (i32, i32, i32, i32), // x, y, width and height from the bottom-left
+    atlas: Vec<u8>,
+    blend_mode: BlendMode,
+}
+
+impl SoftwareRenderer {
+    pub fn new(camera: &Camera) -> Self {
+        let mut renderer = SoftwareRenderer {
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            projection: [0.0; 16],
+            viewport: (0, 0, 0, 0),
+            atlas: atlas::generate(),
+            blend_mode: BlendMode::Alpha,
+        };
+        renderer.set_camera(camera);
+        renderer
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The whole frame as RGBA bytes, row by row from the top.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// RGBA of the pixel at column `x` and row `y`, counted from the top-left corner.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        [
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+            self.pixels[index + 3],
+        ]
+    }
+
+    /// Projects a vertex to the viewport, in pixels from the top-left corner.
+    fn to_pixel_space(&self, vertex: &Vertex) -> (f32, f32) {
+        let p = &self.projection;
+        let clip_x = p[0] * vertex.x + p[4] * vertex.y + p[12];
+        let clip_y = p[1] * vertex.x + p[5] * vertex.y + p[13];
+        let (x, y, width, height) = self.viewport;
+        (
+            x as f32 + (clip_x + 1.0) * 0.5 * width as f32,
+            self.height as f32 - (y as f32 + (clip_y + 1.0) * 0.5 * height as f32),
+        )
+    }
+
+    /// Atlas texel nearest to `uv`, as colour components from 0.0 to 1.0.
+    fn sample(&self, uv: [f32; 2]) -> [f32; 4] {
+        let x = ((uv[0] * ATLAS_WIDTH as f32) as u32).min(ATLAS_WIDTH - 1);
+        let y = ((uv[1] * ATLAS_HEIGHT as f32) as u32).min(ATLAS_HEIGHT - 1);
+        let index = ((y * ATLAS_WIDTH + x) * 4) as usize;
+        let mut texel = [0.0; 4];
+        for (channel, value) in texel.iter_mut().enumerate() {
+            *value = self.atlas[index + channel] as f32 / 255.0;
+        }
+        texel
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: [f32; 4]) {
+        let index = ((y * self.width + x) * 4) as usize;
+        let alpha = color[3].clamp(0.0, 1.0);
+        let pixel = &mut self.pixels[index..index + 4];
+        for (value, &channel) in pixel.iter_mut().zip(color.iter()) {
+            let source = channel.clamp(0.0, 1.0);
+            let destination = *value as f32 / 255.0;
+            let blended = match self.blend_mode {
+                BlendMode::Alpha => source * alpha + destination * (1.0 - alpha),
+                BlendMode::Additive => (source * alpha + destination).min(1.0),
+            };
+            *value = (blended * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Twice the signed area of the triangle (`a`, `b`, `p`).
+fn edge(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// Tie-break for pixel centres exactly on an edge. Neighbouring triangles walk a shared edge in
+/// opposite directions, so exactly one of them owns those pixels and nothing is blended twice.
+fn owns_edge(from: (f32, f32), to: (f32, f32)) -> bool {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    dy < 0.0 || (dy == 0.0 && dx > 0.0)
+}
+
+impl Renderer for SoftwareRenderer {
+    fn set_camera(&mut self, camera: &Camera) {
+        let (width, height) = camera.screen_size();
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.pixels = vec![0; (width * height * 4) as usize];
+        }
+        self.projection = camera.projection();
+        self.viewport = camera.viewport();
+    }
+
+    fn clear(&mut self, color: [f32; 4]) {
+        self.blend_mode = BlendMode::Alpha;
+        let rgba: Vec<u8> = color
+            .iter()
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+
+    fn triangle(&mut self, a: Vertex, b: Vertex, c: Vertex) {
+        // Wind every triangle the same way so the edge tie-break is consistent
+        let (b, c) = if edge(
+            self.to_pixel_space(&a),
+            self.to_pixel_space(&b),
+            self.to_pixel_space(&c),
+        ) < 0.0
+        {
+            (c, b)
+        } else {
+            (b, c)
+        };
+        let (pa, pb, pc) = (
+            self.to_pixel_space(&a),
+            self.to_pixel_space(&b),
+            self.to_pixel_space(&c),
+        );
+        let area = edge(pa, pb, pc);
+        if area <= 0.0 {
+            return; // Degenerate
+        }
+
+        // Clip to the viewport, whose rows are counted from the bottom
+        let (viewport_x, viewport_y, viewport_width, viewport_height) = self.viewport;
+        let (left, right) = (viewport_x, viewport_x + viewport_width);
+        let top = self.height as i32 - (viewport_y + viewport_height);
+        let bottom = self.height as i32 - viewport_y;
+        let clamp =
+            |value: f32, low: i32, high: i32| value.max(low.max(0) as f32).min(high as f32) as u32;
+        let clamp_x = |value: f32| clamp(value, left, right.min(self.width as i32));
+        let clamp_y = |value: f32| clamp(value, top, bottom.min(self.height as i32));
+        let min_x = clamp_x(pa.0.min(pb.0).min(pc.0).floor());
+        let max_x = clamp_x(pa.0.max(pb.0).max(pc.0).ceil());
+        let min_y = clamp_y(pa.1.min(pb.1).min(pc.1).floor());
+        let max_y = clamp_y(pa.1.max(pb.1).max(pc.1).ceil());
+
+        let inside = |weight: f32, from, to| weight > 0.0 || (weight == 0.0 && owns_edge(from, to));
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let p = (x as f32 + 0.5, y as f32 + 0.5);
+                let weight_a = edge(pb, pc, p);
+                let weight_b = edge(pc, pa, p);
+                let weight_c = edge(pa, pb, p);
+                if !(inside(weight_a, pb, pc)
+                    && inside(weight_b, pc, pa)
+                    && inside(weight_c, pa, pb))
+                {
+                    continue;
+                }
+
+                let interpolate =
+                    |a: f32, b: f32, c: f32| (a * weight_a + b * weight_b + c * weight_c) / area;
+                let texel = self.sample([
+                    interpolate(a.uv[0], b.uv[0], c.uv[0]),
+                    interpolate(a.uv[1], b.uv[1], c.uv[1]),
+                ]);
+                let mut color = [0.0; 4];
+                for (channel, value) in color.iter_mut().enumerate() {
+                    *value = interpolate(a.color[channel], b.color[channel], c.color[channel])
+                        * texel[channel];
+                }
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    fn present(&mut self) {}
+
+    fn set_blend(&mut self, blend: BlendMode) {
+        self.blend_mode = blend;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atlas::Sprite;
+    use crate::camera::FitMode;
+    use crate::playfield::HUD_TEXT_SIZE;
+    use crate::playfield::{draw_bricks, draw_message, draw_playfield, hud_bounds, Brick};
+    use crate::text::{self, TextStyle};
+    use crate::theme;
+    use std::path::PathBuf;
+
+    const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+    /// Renders into a `width` by `height` canvas showing the 2 by 2 world.
+    fn render(
+        width: u32,
+        height: u32,
+        draw: impl FnOnce(&mut SoftwareRenderer, &Camera),
+    ) -> SoftwareRenderer {
+        let mut camera = Camera::new(2.0, 2.0, FitMode::Letterbox);
+        camera.resize(width, height);
+        let mut renderer = SoftwareRenderer::new(&camera);
+        draw(&mut renderer, &camera);
+        renderer.present();
+        renderer
+    }
+
+    /// `color` as stored in the frame.
+    fn to_bytes(color: [f32; 4]) -> [u8; 4] {
+        color.map(|channel| (channel * 255.0).round() as u8)
+    }
+
+    /// Compares the frame with `golden/<name>.rgba`. Run with `UPDATE_GOLDEN=1` to rewrite the
+    /// stored images after an intended change, then review them before committing.
+    fn assert_golden(name: &str, renderer: &SoftwareRenderer) {
+        let path: PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "src",
+            "renderer",
+            "golden",
+            &format!("{}.rgba", name),
+        ]
+        .iter()
+        .collect();
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, renderer.pixels()).unwrap();
+            return;
+        }
+        let golden = std::fs::read(&path)
+            .unwrap_or_else(|error| panic!("can't read {}: {}", path.display(), error));
+        assert_eq!(
+            golden.len(),
+            renderer.pixels().len(),
+            "{} has a different size",
+            name
+        );
+        let differing = golden
+            .chunks_exact(4)
+            .zip(renderer.pixels().chunks_exact(4))
+            .position(|(expected, actual)| expected != actual);
+        if let Some(index) = differing {
+            let (x, y) = (
+                index as u32 % renderer.width(),
+                index as u32 / renderer.width(),
+            );
+            panic!(
+                "{} differs from its golden image first at ({}, {}): {:?}",
+                name,
+                x,
+                y,
+                renderer.pixel(x, y)
+            );
+        }
+    }
+
+    #[test]
+    fn cleared_frame() {
+        let renderer = render(8, 8, |renderer, _| renderer.clear([0.2, 0.4, 0.6, 1.0]));
+        assert_eq!(renderer.pixel(0, 0), [51, 102, 153, 255]);
+        assert_eq!(renderer.pixel(7, 7), [51, 102, 153, 255]);
+        assert_golden("cleared", &renderer);
+    }
+
+    #[test]
+    fn alpha_blended_quad() {
+        let renderer = render(16, 16, |renderer, _| {
+            renderer.clear(BLACK);
+            renderer.rect(-1.0, -1.0, 0.5, 0.5, [0.0, 0.0, 1.0, 1.0]);
+            renderer.rect(-0.5, -0.5, 1.0, 1.0, [1.0, 0.0, 0.0, 0.5]);
+        });
+        // Half red over blue where they overlap, half red over black elsewhere
+        assert_eq!(renderer.pixel(8, 8), [128, 0, 128, 191]);
+        assert_eq!(renderer.pixel(15, 0), [128, 0, 0, 191]);
+        assert_eq!(renderer.pixel(0, 15), [0, 0, 255, 255]);
+        assert_golden("alpha_quad", &renderer);
+    }
+
+    #[test]
+    fn additive_quad() {
+        let renderer = render(16, 16, |renderer, _| {
+            renderer.clear(BLACK);
+            renderer.set_blend(BlendMode::Additive);
+            renderer.rect(-1.0, -1.0, 0.5, 0.5, [0.6, 0.2, 0.0, 1.0]);
+            renderer.rect(-0.5, -0.5, 1.0, 1.0, [0.6, 0.2, 0.0, 1.0]);
+        });
+        // Overlapping light adds up and saturates
+        assert_eq!(renderer.pixel(8, 8), [255, 102, 0, 255]);
+        assert_eq!(renderer.pixel(15, 0), [153, 51, 0, 255]);
+        assert_golden("additive_quad", &renderer);
+    }
+
+    #[test]
+    fn letterbox_viewport() {
+        let renderer = render(16, 8, |renderer, _| {
+            renderer.clear(BLACK);
+            renderer.rect(-2.0, -2.0, 2.0, 2.0, [1.0, 1.0, 1.0, 1.0]);
+        });
+        // The square world is centred in an 8 by 8 viewport and nothing spills into the bars
+        assert_eq!(renderer.pixel(3, 4), [0, 0, 0, 255]);
+        assert_eq!(renderer.pixel(4, 4), [255, 255, 255, 255]);
+        assert_eq!(renderer.pixel(11, 4), [255, 255, 255, 255]);
+        assert_eq!(renderer.pixel(12, 4), [0, 0, 0, 255]);
+        assert_golden("letterbox", &renderer);
+    }
+
+    #[test]
+    fn game_frame() {
+        let theme = &theme::DARK;
+        // A row of bricks from untouched to nearly broken, one of them just hit
+        let bricks: Vec<Brick> = (0..4)
+            .map(|column| {
+                let left = -0.9 + column as f32 * 0.45;
+                Brick {
+                    left,
+                    bottom: 0.5,
+                    right: left + 0.4,
+                    top: 0.7,
+                    health: 4 - column,
+                    max_health: 4,
+                    hit_timer: if column == 2 { 0.1 } else { 0.0 },
+                }
+            })
+            .collect();
+        let renderer = render(160, 128, |renderer, camera| {
+            renderer.clear(theme.clear);
+            draw_playfield(renderer, theme, camera);
+            draw_bricks(renderer, &bricks, theme);
+            renderer.sprite(Sprite::Bullet, -0.05, -0.2, 0.05, -0.1, theme.text);
+            let (left, _, top) = hud_bounds(camera);
+            let style = TextStyle::new(HUD_TEXT_SIZE, theme.text);
+            text::draw_text(renderer, "SCORE 120", left + 0.05, top - 0.1, &style);
+            draw_message(renderer, "STAGE 2", 1.0, theme);
+        });
+        // The letterbox bars keep the clear colour and the ground covers the bottom
+        assert_eq!(renderer.pixel(0, 64), to_bytes(theme.clear));
+        assert_eq!(renderer.pixel(80, 127), to_bytes(theme.ground));
+        assert_golden("game_frame", &renderer);
+    }
+}
@@ -0,0 +1,491 @@
+use glow::HasContext;
+use web_sys::HtmlImageElement;
+
+use super::post::PostProcessor;
+use super::{BlendMode, PostEffects, Renderer, Vertex};
+use crate::atlas::{self, ATLAS_HEIGHT, ATLAS_WIDTH};
+use crate::camera::Camera;
+
+// ### WebGL2 Backend
+
+const FLOATS_PER_VERTEX: usize = 9;
+const INITIAL_CAPACITY: usize = 4096; // Vertices the buffer holds before it has to grow
+const LOW_END_CORES: f64 = 2.0; // Devices with this many cores or fewer skip post-processing
+const SLOW_FRAME_MS: f64 = 50.0; // Average frame time above which post-processing is disabled
+const FRAME_TIME_SMOOTHING: f64 = 0.02; // Weight of each new frame in the running average
+const FRAME_TIME_WARM_UP: u32 = 120; // Frames timed before the average is trusted
+const MAX_FRAME_GAP_MS: f64 = 1000.0; // Longer gaps are pauses or hidden tabs, not slow frames
+
+const VERTEX_SHADER_SOURCE: &str = "#version 300 es
+in vec2 position;
+in vec2 uv;
+in vec4 color;
+in float glow;
+out vec2 v_uv;
+out vec4 v_color;
+out float v_glow;
+uniform mat4 projection;
+void main() {
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+    v_uv = uv;
+    v_color = color;
+    v_glow = glow;
+}
+";
+
+const FRAGMENT_SHADER_SOURCE: &str = "#version 300 es
+precision mediump float;
+in vec2 v_uv;
+in vec4 v_color;
+in float v_glow;
+uniform sampler2D atlas;
+layout(location = 0) out vec4 fragColor;
+layout(location = 1) out vec4 glowColor;
+void main() {
+    fragColor = v_color * texture(atlas, v_uv);
+    // Shapes that don't glow still cover the glow behind them
+    glowColor = vec4(fragColor.rgb * v_glow, fragColor.a);
+}
+";
+
+/// Draws with WebGL2, batching every shape in a frame into one triangle list that is drawn
+/// with a single call from a persistent vertex buffer.
+///
+/// Shapes are transformed on the CPU and drawn in the order they are added. Every shape samples
+/// the sprite atlas, which starts as the built-in atlas and can be replaced by an image.
+///
+/// Unless the device looks too slow, frames are drawn off-screen and presented through a
+/// post-processing pass chain that adds bloom, chromatic flashes and a screen filter.
+pub struct WebGlRenderer {
+    gl: glow::Context,
+    batch: Batch,
+    pending_atlas: Option<HtmlImageElement>, // Atlas image still loading
+    loaded_atlas: Option<HtmlImageElement>,  // Atlas image in use, uploaded again on restore
+    vertices: Vec<(Vertex, f32)>,            // Vertices with the glow they were drawn with
+    bytes: Vec<u8>,                          // Reused staging area for uploads
+    glow: f32,
+    viewport: (i32, i32, i32, i32), // Camera viewport, restored after post-processing
+    post: Option<PostProcessor>,    // None when post-processing is unavailable or disabled
+    effects: PostEffects,
+    last_present: f64,     // Time of the last present in milliseconds
+    average_frame_ms: f64, // Running average of the time between presents
+    frames_timed: u32,     // Frames counted into the average, up to FRAME_TIME_WARM_UP
+}
+
+/// GL objects for drawing the batch. They die with the context and are rebuilt on restore.
+struct Batch {
+    program: glow::Program,
+    projection_location: glow::UniformLocation,
+    vertex_array: glow::VertexArray,
+    vertex_buffer: glow::Buffer,
+    atlas_texture: glow::Texture, // Holds the built-in atlas until an image is uploaded
+    capacity: usize,              // Vertices the GPU buffer can hold
+}
+
+impl Batch {
+    unsafe fn new(gl: &glow::Context) -> Result<Self, String> {
+        gl.enable(glow::BLEND);
+        gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
+
+        let program = compile_program(gl, VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE)?;
+        let attrib = |name: &str| {
+            gl.get_attrib_location(program, name)
+                .ok_or_else(|| format!("Shader attribute {} not found", name))
+        };
+        let position_attrib = attrib("position")?;
+        let uv_attrib = attrib("uv")?;
+        let color_attrib = attrib("color")?;
+        let glow_attrib = attrib("glow")?;
+        let projection_location = gl
+            .get_uniform_location(program, "projection")
+            .ok_or("Shader uniform projection not found")?;
+        let atlas_location = gl
+            .get_uniform_location(program, "atlas")
+            .ok_or("Shader uniform atlas not found")?;
+
+        let vertex_array = gl.create_vertex_array()?;
+        gl.bind_vertex_array(Some(vertex_array));
+        let vertex_buffer = gl.create_buffer()?;
+        gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+        gl.buffer_data_size(
+            glow::ARRAY_BUFFER,
+            (INITIAL_CAPACITY * FLOATS_PER_VERTEX * 4) as i32,
+            glow::DYNAMIC_DRAW,
+        );
+
+        let stride = (FLOATS_PER_VERTEX * 4) as i32;
+        gl.vertex_attrib_pointer_f32(position_attrib, 2, glow::FLOAT, false, stride, 0);
+        gl.enable_vertex_attrib_array(position_attrib);
+        gl.vertex_attrib_pointer_f32(uv_attrib, 2, glow::FLOAT, false, stride, 2 * 4);
+        gl.enable_vertex_attrib_array(uv_attrib);
+        gl.vertex_attrib_pointer_f32(color_attrib, 4, glow::FLOAT, false, stride, 4 * 4);
+        gl.enable_vertex_attrib_array(color_attrib);
+        gl.vertex_attrib_pointer_f32(glow_attrib, 1, glow::FLOAT, false, stride, 8 * 4);
+        gl.enable_vertex_attrib_array(glow_attrib);
+
+        let atlas_texture = gl.create_texture()?;
+        gl.active_texture(glow::TEXTURE0);
+        gl.bind_texture(glow::TEXTURE_2D, Some(atlas_texture));
+        gl.tex_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            glow::RGBA as i32,
+            ATLAS_WIDTH as i32,
+            ATLAS_HEIGHT as i32,
+            0,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            Some(&atlas::generate()),
+        );
+        set_atlas_filtering(gl);
+        gl.use_program(Some(program));
+        gl.uniform_1_i32(Some(&atlas_location), 0);
+
+        Ok(Batch {
+            program,
+            projection_location,
+            vertex_array,
+            vertex_buffer,
+            atlas_texture,
+            capacity: INITIAL_CAPACITY,
+        })
+    }
+
+    unsafe fn delete(&self, gl: &glow::Context) {
+        gl.delete_program(self.program);
+        gl.delete_vertex_array(self.vertex_array);
+        gl.delete_buffer(self.vertex_buffer);
+        gl.delete_texture(self.atlas_texture);
+    }
+}
+
+impl WebGlRenderer {
+    /// Takes over `gl`, drawing through `camera`. Fails with the driver's message if the shaders
+    /// or buffers can't be created.
+    pub fn new(gl: glow::Context, camera: &Camera) -> Result<Self, String> {
+        let batch = unsafe { Batch::new(&gl)? };
+        let (width, height) = camera.screen_size();
+        let post = create_post_processor(&gl, width as i32, height as i32);
+        let mut renderer = WebGlRenderer {
+            gl,
+            batch,
+            pending_atlas: None,
+            loaded_atlas: None,
+            vertices: Vec::with_capacity(INITIAL_CAPACITY),
+            bytes: Vec::new(),
+            glow: 0.0,
+            viewport: (0, 0, width as i32, height as i32),
+            post,
+            effects: PostEffects::default(),
+            last_present: js_sys::Date::now(),
+            average_frame_ms: 0.0,
+            frames_timed: 0,
+        };
+        renderer.set_camera(camera);
+        Ok(renderer)
+    }
+
+    /// Rebuilds every GL object after the browser restored a lost context. Objects from the old
+    /// context are gone, so nothing is deleted.
+    pub fn restore(&mut self, camera: &Camera) -> Result<(), String> {
+        self.vertices.clear();
+        self.batch = unsafe { Batch::new(&self.gl)? };
+        if self.post.is_some() {
+            let (width, height) = camera.screen_size();
+            self.post = create_post_processor(&self.gl, width as i32, height as i32);
+        }
+        if self.pending_atlas.is_none() {
+            self.pending_atlas = self.loaded_atlas.take();
+        }
+        self.set_camera(camera);
+        Ok(())
+    }
+
+    /// Starts loading an atlas image from `url`. It must follow the built-in atlas's layout and
+    /// replaces it once loaded; until then, or if it fails to load, the built-in atlas is used.
+    pub fn load_atlas_image(&mut self, url: &str) {
+        match HtmlImageElement::new() {
+            Ok(image) => {
+                image.set_cross_origin(Some("anonymous"));
+                image.set_src(url);
+                self.pending_atlas = Some(image);
+            }
+            Err(_) => log::warn!("Cannot create an image to load the atlas from {}", url),
+        }
+    }
+
+    /// Uploads the atlas image once the browser has finished loading it.
+    fn upload_loaded_atlas(&mut self) {
+        let loaded = match &self.pending_atlas {
+            Some(image) if image.complete() => self.pending_atlas.take().unwrap(),
+            _ => return,
+        };
+        if loaded.natural_width() != ATLAS_WIDTH || loaded.natural_height() != ATLAS_HEIGHT {
+            log::warn!(
+                "Atlas image {} failed to load or is not {}x{}, keeping the built-in atlas",
+                loaded.src(),
+                ATLAS_WIDTH,
+                ATLAS_HEIGHT
+            );
+            return;
+        }
+        unsafe {
+            self.gl.active_texture(glow::TEXTURE0);
+            self.gl
+                .bind_texture(glow::TEXTURE_2D, Some(self.batch.atlas_texture));
+            // glow only uploads HTML images on the web, where they are the only kind that loads
+            #[cfg(target_arch = "wasm32")]
+            self.gl.tex_image_2d_with_html_image(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                &loaded,
+            );
+            set_atlas_filtering(&self.gl);
+        }
+        self.loaded_atlas = Some(loaded);
+    }
+
+    /// Uploads everything drawn since the last flush and draws it in one call.
+    fn flush(&mut self) {
+        self.upload_loaded_atlas();
+        let gl = &self.gl;
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        self.bytes.clear();
+        for (vertex, glow) in &self.vertices {
+            let values = [vertex.x, vertex.y, vertex.uv[0], vertex.uv[1]];
+            let values = values.iter().chain(vertex.color.iter());
+            for value in values.chain(std::iter::once(glow)) {
+                self.bytes.extend_from_slice(&value.to_ne_bytes());
+            }
+        }
+
+        unsafe {
+            let batch = &mut self.batch;
+            gl.use_program(Some(batch.program));
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, Some(batch.atlas_texture));
+            gl.bind_vertex_array(Some(batch.vertex_array));
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(batch.vertex_buffer));
+            if self.vertices.len() > batch.capacity {
+                // Grow geometrically so busy frames don't reallocate every time
+                batch.capacity = self.vertices.len().next_power_of_two();
+                gl.buffer_data_size(
+                    glow::ARRAY_BUFFER,
+                    (batch.capacity * FLOATS_PER_VERTEX * 4) as i32,
+                    glow::DYNAMIC_DRAW,
+                );
+            }
+            gl.buffer_sub_data_u8_slice(glow::ARRAY_BUFFER, 0, &self.bytes);
+            gl.draw_arrays(glow::TRIANGLES, 0, self.vertices.len() as i32);
+        }
+
+        self.vertices.clear();
+    }
+
+    /// Drops the post-processing pass chain for good, drawing straight to the canvas.
+    fn disable_post_processing(&mut self, reason: &str) {
+        if let Some(post) = self.post.take() {
+            log::warn!("Disabling post-processing: {}", reason);
+            post.delete(&self.gl);
+        }
+    }
+
+    /// Disables post-processing if frames have been slow for a while.
+    fn watch_frame_time(&mut self) {
+        let now = js_sys::Date::now();
+        let frame_ms = now - self.last_present;
+        self.last_present = now;
+        if frame_ms > MAX_FRAME_GAP_MS {
+            return;
+        }
+        // Seed the average with the first frame, then smooth out spikes and capped frame rates
+        self.average_frame_ms = if self.frames_timed == 0 {
+            frame_ms
+        } else {
+            self.average_frame_ms + (frame_ms - self.average_frame_ms) * FRAME_TIME_SMOOTHING
+        };
+        self.frames_timed = (self.frames_timed + 1).min(FRAME_TIME_WARM_UP);
+        if self.frames_timed >= FRAME_TIME_WARM_UP && self.average_frame_ms > SLOW_FRAME_MS {
+            self.disable_post_processing("frames are too slow");
+        }
+    }
+}
+
+/// Releases the GL objects when the game is destroyed. After a context loss they are already
+/// gone and deleting them does nothing.
+impl Drop for WebGlRenderer {
+    fn drop(&mut self) {
+        unsafe { self.batch.delete(&self.gl) };
+        if let Some(post) = self.post.take() {
+            post.delete(&self.gl);
+        }
+    }
+}
+
+impl Renderer for WebGlRenderer {
+    fn set_camera(&mut self, camera: &Camera) {
+        self.flush();
+        let (screen_width, screen_height) = camera.screen_size();
+        let resized = match &mut self.post {
+            Some(post) => post.resize(&self.gl, screen_width as i32, screen_height as i32),
+            None => Ok(()),
+        };
+        if let Err(error) = resized {
+            self.disable_post_processing(&error);
+        }
+        self.viewport = camera.viewport();
+        let (x, y, width, height) = self.viewport;
+        unsafe {
+            self.gl.viewport(x, y, width, height);
+            self.gl.use_program(Some(self.batch.program));
+            self.gl.uniform_matrix_4_f32_slice(
+                Some(&self.batch.projection_location),
+                false,
+                &camera.projection(),
+            );
+        }
+    }
+
+    fn clear(&mut self, color: [f32; 4]) {
+        self.vertices.clear();
+        self.set_blend(BlendMode::Alpha);
+        if let Some(post) = &self.post {
+            let (x, y, width, height) = self.viewport;
+            post.begin(&self.gl, color);
+            unsafe { self.gl.viewport(x, y, width, height) };
+            return;
+        }
+        unsafe {
+            self.gl.clear_color(color[0], color[1], color[2], color[3]);
+            self.gl.clear(glow::COLOR_BUFFER_BIT);
+        }
+    }
+
+    fn triangle(&mut self, a: Vertex, b: Vertex, c: Vertex) {
+        self.vertices
+            .extend_from_slice(&[(a, self.glow), (b, self.glow), (c, self.glow)]);
+    }
+
+    fn present(&mut self) {
+        self.flush();
+        if let Some(post) = &self.post {
+            post.finish(&self.gl, &self.effects);
+        }
+        self.watch_frame_time();
+    }
+
+    fn set_blend(&mut self, blend: BlendMode) {
+        // Shapes already queued keep the blending they were submitted with
+        self.flush();
+        let destination = match blend {
+            BlendMode::Alpha => glow::ONE_MINUS_SRC_ALPHA,
+            BlendMode::Additive => glow::ONE,
+        };
+        unsafe { self.gl.blend_func(glow::SRC_ALPHA, destination) };
+    }
+
+    fn set_glow(&mut self, glow: f32) {
+        self.glow = glow;
+    }
+
+    fn set_effects(&mut self, effects: PostEffects) {
+        self.effects = effects;
+    }
+}
+
+/// Linear filtering without mipmaps, clamped at the edges, for the bound atlas texture.
+unsafe fn set_atlas_filtering(gl: &glow::Context) {
+    let parameters = [
+        (glow::TEXTURE_MIN_FILTER, glow::LINEAR),
+        (glow::TEXTURE_MAG_FILTER, glow::LINEAR),
+        (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+        (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+    ];
+    for &(parameter, value) in parameters.iter() {
+        gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+    }
+}
+
+/// Creates the post-processing pass chain unless the device looks too slow or can't render to
+/// textures.
+fn create_post_processor(gl: &glow::Context, width: i32, height: i32) -> Option<PostProcessor> {
+    let cores = web_sys::window().map_or(0.0, |window| window.navigator().hardware_concurrency());
+    if cores <= LOW_END_CORES {
+        log::info!("Skipping post-processing on a device with {} cores", cores);
+        return None;
+    }
+    match PostProcessor::new(gl, width, height) {
+        Ok(post) => Some(post),
+        Err(error) => {
+            log::warn!("Post-processing unavailable: {}", error);
+            unsafe { gl.bind_framebuffer(glow::FRAMEBUFFER, None) };
+            None
+        }
+    }
+}
+
+/// Compiles and links a shader program, failing with the driver's log.
+pub(super) unsafe fn compile_program(
+    gl: &glow::Context,
+    vertex_shader_source: &str,
+    fragment_shader_source: &str,
+) -> Result<glow::Program, String> {
+    let program = gl.create_program()?;
+    let shader_sources = [
+        (glow::VERTEX_SHADER, vertex_shader_source),
+        (glow::FRAGMENT_SHADER, fragment_shader_source),
+    ];
+
+    let mut shaders = Vec::new();
+    let linked = link_program(gl, program, &shader_sources, &mut shaders);
+
+    // The shaders are only needed until the program is linked, whether or not that worked
+    for shader in shaders {
+        gl.detach_shader(program, shader);
+        gl.delete_shader(shader);
+    }
+    if let Err(error) = linked {
+        gl.delete_program(program);
+        return Err(error);
+    }
+
+    Ok(program)
+}
+
+/// Compiles and attaches each shader to `program` and links it, adding every shader created to
+/// `shaders` so the caller can delete them even when this fails.
+unsafe fn link_program(
+    gl: &glow::Context,
+    program: glow::Program,
+    shader_sources: &[(u32, &str)],
+    shaders: &mut Vec<glow::Shader>,
+) -> Result<(), String> {
+    for (shader_type, source) in shader_sources.iter() {
+        let shader = gl.create_shader(*shader_type)?;
+        gl.attach_shader(program, shader);
+        shaders.push(shader);
+        gl.shader_source(shader, source);
+        gl.compile_shader(shader);
+        if !gl.get_shader_compile_status(shader) {
+            return Err(format!(
+                "Shader compilation failed: {}",
+                gl.get_shader_info_log(shader)
+            ));
+        }
+    }
+
+    gl.link_program(program);
+    if !gl.get_program_link_status(program) {
+        return Err(format!(
+            "Shader linking failed: {}",
+            gl.get_program_info_log(program)
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement, MouseEvent};
+
+use crate::upgrades::{Progress, UPGRADES};
+
+// ### Shop Between Stages
+
+/// Something the player asked for in the shop.
+pub enum ShopAction {
+    Buy(usize), // Index into `UPGRADES`
+    Continue,
+}
+
+/// DOM panel listing upgrades between stages.
+///
+/// Clicks are queued as `ShopAction`s and drained by the game loop, alongside the number keys
+/// and Enter handled there.
+pub struct ShopPanel {
+    element: HtmlElement,
+    actions: Rc<RefCell<VecDeque<ShopAction>>>,
+    on_click: Closure<dyn FnMut(MouseEvent)>,
+}
+
+impl ShopPanel {
+    pub fn new(parent: &Element) -> Self {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let element = document
+            .create_element("div")
+            .unwrap()
+            .dyn_into::<HtmlElement>()
+            .unwrap();
+        element.set_class_name("shop"); // Not an id, as a page may run several games
+        let style = element.style();
+        style.set_property("display", "none").unwrap();
+        style.set_property("position", "absolute").unwrap();
+        style.set_property("top", "50%").unwrap();
+        style.set_property("left", "50%").unwrap();
+        style
+            .set_property("transform", "translate(-50%, -50%)")
+            .unwrap();
+        style.set_property("padding", "16px 24px").unwrap();
+        style
+            .set_property("background", "rgba(0, 0, 0, 0.8)")
+            .unwrap();
+        style.set_property("color", "white").unwrap();
+        style.set_property("font-family", "monospace").unwrap();
+        style.set_property("z-index", "3").unwrap();
+        parent.append_child(&element).expect("Append shop panel");
+
+        let actions = Rc::new(RefCell::new(VecDeque::new()));
+        let queue = actions.clone();
+        let on_click = Closure::wrap(Box::new(move |event: MouseEvent| {
+            let action = event
+                .target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .and_then(|target| target.get_attribute("data-action"));
+            match action.as_deref() {
+                Some("continue") => queue.borrow_mut().push_back(ShopAction::Continue),
+                Some(action) => {
+                    if let Some(Ok(index)) = action.strip_prefix("buy-").map(str::parse) {
+                        queue.borrow_mut().push_back(ShopAction::Buy(index));
+                    }
+                }
+                None => {}
+            }
+        }) as Box<dyn FnMut(MouseEvent)>);
+        element
+            .add_event_listener_with_callback("click", on_click.as_ref().unchecked_ref())
+            .unwrap();
+
+        ShopPanel {
+            element,
+            actions,
+            on_click,
+        }
+    }
+
+    /// Shows the panel with the current prices and levels.
+    pub fn show(&self, progress: &Progress, cleared_stage: u32) {
+        let mut html = format!(
+            "<h2>Stage {} cleared</h2><p>Coins: {}</p>",
+            cleared_stage, progress.currency
+        );
+        for (index, def) in UPGRADES.iter().enumerate() {
+            let level = progress.level(def.stat);
+            let status = if !progress.is_unlocked(index) {
+                "locked".to_string()
+            } else {
+                match progress.next_cost(index) {
+                    Some(cost) => format!(
+                        "<button data-action=\"buy-{}\"{}>Buy ({})</button>",
+                        index,
+                        if cost > progress.currency {
+                            " disabled"
+                        } else {
+                            ""
+                        },
+                        cost
+                    ),
+                    None => "max".to_string(),
+                }
+            };
+            html.push_str(&format!(
+                "<p>[{}] {} {}/{} {}</p>",
+                index + 1,
+                def.name,
+                level,
+                def.max_level(),
+                status
+            ));
+        }
+        html.push_str("<button data-action=\"continue\">Next stage [Enter]</button>");
+        self.element.set_inner_html(&html);
+        self.element
+            .style()
+            .set_property("display", "block")
+            .unwrap();
+    }
+
+    pub fn hide(&self) {
+        self.element
+            .style()
+            .set_property("display", "none")
+            .unwrap();
+    }
+
+    /// Removes and returns the clicks queued since the last call.
+    pub fn take_actions(&self) -> Vec<ShopAction> {
+        self.actions.borrow_mut().drain(..).collect()
+    }
+}
+
+impl Drop for ShopPanel {
+    fn drop(&mut self) {
+        let _ = self
+            .element
+            .remove_event_listener_with_callback("click", self.on_click.as_ref().unchecked_ref());
+        self.element.remove();
+    }
+}
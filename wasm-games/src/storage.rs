@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+// ### Storage Abstraction
+
+/// Key-value persistence for data that should survive between sessions.
+pub trait Storage {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, value: &str);
+}
+
+/// Opens `localStorage`, falling back to in-memory storage when it is unavailable (e.g.
+/// disabled in private browsing) so the game still runs, just without persistence.
+pub fn open_storage() -> Box<dyn Storage> {
+    match LocalStorage::open() {
+        Some(storage) => Box::new(storage),
+        None => {
+            log::warn!("localStorage unavailable, progress will not be saved");
+            Box::new(MemoryStorage::default())
+        }
+    }
+}
+
+/// Storage backed by the browser's `localStorage`.
+pub struct LocalStorage {
+    storage: web_sys::Storage,
+}
+
+impl LocalStorage {
+    pub fn open() -> Option<Self> {
+        let storage = web_sys::window()?.local_storage().ok()??;
+        Some(LocalStorage { storage })
+    }
+}
+
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.storage.get_item(key).ok().flatten()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        if let Err(err) = self.storage.set_item(key, value) {
+            log::warn!("Failed to save {}: {:?}", key, err);
+        }
+    }
+}
+
+/// Storage that only lives as long as the value itself.
+#[derive(Default)]
+pub struct MemoryStorage {
+    values: HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn save(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::renderer::Renderer;
+
+// ### Bitmap Font
+
+const GLYPH_COLUMNS: u32 = 5;
+const GLYPH_ROWS: u32 = 7;
+const GLYPH_ADVANCE: u32 = GLYPH_COLUMNS + 1; // One column of spacing between characters
+const LINE_ADVANCE: u32 = GLYPH_ROWS + 3; // Rows from one baseline to the next
+
+/// 5x7 glyphs, one byte per row from the top with the leftmost column in bit 4.
+///
+/// Lower-case letters are drawn with the upper-case glyphs.
+const GLYPHS: [(char, [u8; 7]); 64] = [
+    (' ', [0, 0, 0, 0, 0, 0, 0]),
+    ('A', [14, 17, 17, 31, 17, 17, 17]),
+    ('B', [30, 17, 17, 30, 17, 17, 30]),
+    ('C', [14, 17, 16, 16, 16, 17, 14]),
+    ('D', [28, 18, 17, 17, 17, 18, 28]),
+    ('E', [31, 16, 16, 30, 16, 16, 31]),
+    ('F', [31, 16, 16, 30, 16, 16, 16]),
+    ('G', [14, 17, 16, 23, 17, 17, 15]),
+    ('H', [17, 17, 17, 31, 17, 17, 17]),
+    ('I', [14, 4, 4, 4, 4, 4, 14]),
+    ('J', [7, 2, 2, 2, 2, 18, 12]),
+    ('K', [17, 18, 20, 24, 20, 18, 17]),
+    ('L', [16, 16, 16, 16, 16, 16, 31]),
+    ('M', [17, 27, 21, 21, 17, 17, 17]),
+    ('N', [17, 17, 25, 21, 19, 17, 17]),
+    ('O', [14, 17, 17, 17, 17, 17, 14]),
+    ('P', [30, 17, 17, 30, 16, 16, 16]),
+    ('Q', [14, 17, 17, 17, 21, 18, 13]),
+    ('R', [30, 17, 17, 30, 20, 18, 17]),
+    ('S', [15, 16, 16, 14, 1, 1, 30]),
+    ('T', [31, 4, 4, 4, 4, 4, 4]),
+    ('U', [17, 17, 17, 17, 17, 17, 14]),
+    ('V', [17, 17, 17, 17, 17, 10, 4]),
+    ('W', [17, 17, 17, 21, 21, 21, 10]),
+    ('X', [17, 17, 10, 4, 10, 17, 17]),
+    ('Y', [17, 17, 10, 4, 4, 4, 4]),
+    ('Z', [31, 1, 2, 4, 8, 16, 31]),
+    ('0', [14, 17, 19, 21, 25, 17, 14]),
+    ('1', [4, 12, 4, 4, 4, 4, 14]),
+    ('2', [14, 17, 1, 2, 4, 8, 31]),
+    ('3', [31, 2, 4, 2, 1, 17, 14]),
+    ('4', [2, 6, 10, 18, 31, 2, 2]),
+    ('5', [31, 16, 30, 1, 1, 17, 14]),
+    ('6', [6, 8, 16, 30, 17, 17, 14]),
+    ('7', [31, 1, 2, 4, 8, 8, 8]),
+    ('8', [14, 17, 17, 14, 17, 17, 14]),
+    ('9', [14, 17, 17, 15, 1, 2, 12]),
+    ('.', [0, 0, 0, 0, 0, 12, 12]),
+    (',', [0, 0, 0, 0, 12, 4, 8]),
+    (':', [0, 12, 12, 0, 12, 12, 0]),
+    (';', [0, 12, 12, 0, 12, 4, 8]),
+    ('!', [4, 4, 4, 4, 4, 0, 4]),
+    ('?', [14, 17, 1, 2, 4, 0, 4]),
+    ('-', [0, 0, 0, 31, 0, 0, 0]),
+    ('+', [0, 4, 4, 31, 4, 4, 0]),
+    ('=', [0, 0, 31, 0, 31, 0, 0]),
+    ('*', [0, 4, 21, 14, 21, 4, 0]),
+    ('/', [0, 1, 2, 4, 8, 16, 0]),
+    ('%', [24, 25, 2, 4, 8, 19, 3]),
+    ('(', [2, 4, 8, 8, 8, 4, 2]),
+    (')', [8, 4, 2, 2, 2, 4, 8]),
+    ('[', [14, 8, 8, 8, 8, 8, 14]),
+    (']', [14, 2, 2, 2, 2, 2, 14]),
+    ('<', [2, 4, 8, 16, 8, 4, 2]),
+    ('>', [8, 4, 2, 1, 2, 4, 8]),
+    ('\'', [4, 4, 8, 0, 0, 0, 0]),
+    ('"', [10, 10, 0, 0, 0, 0, 0]),
+    ('_', [0, 0, 0, 0, 0, 0, 31]),
+    ('#', [10, 10, 31, 10, 31, 10, 10]),
+    ('$', [4, 15, 20, 14, 5, 30, 4]),
+    ('&', [12, 18, 20, 8, 21, 18, 13]),
+    ('@', [14, 17, 1, 13, 21, 21, 14]),
+    ('^', [4, 10, 17, 0, 0, 0, 0]),
+    ('|', [4, 4, 4, 4, 4, 4, 4]),
+];
+
+/// Drawn for characters the font doesn't have.
+const MISSING_GLYPH: [u8; 7] = [31, 17, 17, 17, 17, 17, 31];
+
+fn glyph(character: char) -> &'static [u8; 7] {
+    let character = character.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .find(|(glyph_character, _)| *glyph_character == character)
+        .map_or(&MISSING_GLYPH, |(_, rows)| rows)
+}
+
+// ### Text
+
+/// Horizontal alignment of each line relative to the anchor.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// How text is drawn. `size` is the height of a capital letter in world units.
+#[derive(Clone, Copy)]
+pub struct TextStyle {
+    pub size: f32,
+    pub color: [f32; 4],
+    pub align: Align,
+}
+
+impl TextStyle {
+    pub fn new(size: f32, color: [f32; 4]) -> Self {
+        TextStyle {
+            size,
+            color,
+            align: Align::Left,
+        }
+    }
+
+    pub fn aligned(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+}
+
+fn line_width(line: &str, pixel: f32) -> f32 {
+    let count = line.chars().count() as u32;
+    // No spacing after the last character
+    (count * GLYPH_ADVANCE).saturating_sub(1) as f32 * pixel
+}
+
+/// Draws `text` with the top of its first line at `y`, aligned on `x`. Lines break at `\n`.
+pub fn draw_text(renderer: &mut dyn Renderer, text: &str, x: f32, y: f32, style: &TextStyle) {
+    let pixel = style.size / GLYPH_ROWS as f32;
+    for (line_index, line) in text.lines().enumerate() {
+        let top = y - (line_index as u32 * LINE_ADVANCE) as f32 * pixel;
+        let left = match style.align {
+            Align::Left => x,
+            Align::Center => x - line_width(line, pixel) / 2.0,
+            Align::Right => x - line_width(line, pixel),
+        };
+        for (index, character) in line.chars().enumerate() {
+            let glyph_left = left + (index as u32 * GLYPH_ADVANCE) as f32 * pixel;
+            draw_glyph(
+                renderer,
+                glyph(character),
+                glyph_left,
+                top,
+                pixel,
+                style.color,
+            );
+        }
+    }
+}
+
+/// Draws a glyph as one rectangle per horizontal run of lit pixels.
+fn draw_glyph(
+    renderer: &mut dyn Renderer,
+    rows: &[u8; 7],
+    left: f32,
+    top: f32,
+    pixel: f32,
+    color: [f32; 4],
+) {
+    for (row_index, row) in rows.iter().enumerate() {
+        let row_top = top - row_index as f32 * pixel;
+        let mut column = 0;
+        while column < GLYPH_COLUMNS {
+            if row & (1 << (GLYPH_COLUMNS - 1 - column)) == 0 {
+                column += 1;
+                continue;
+            }
+            let start = column;
+            while column < GLYPH_COLUMNS && row & (1 << (GLYPH_COLUMNS - 1 - column)) != 0 {
+                column += 1;
+            }
+            renderer.rect(
+                left + start as f32 * pixel,
+                row_top - pixel,
+                left + column as f32 * pixel,
+                row_top,
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::storage::Storage;
+
+// ### Themes
+
+pub const THEME_KEY: &str = "brick-breaker-theme"; // Storage key of the chosen theme
+
+/// How bricks are coloured.
+#[derive(Clone, Copy)]
+pub enum BrickColors {
+    /// A rainbow across the playfield, with the hue following each brick's x position.
+    ByPosition { saturation: f32, value: f32 },
+    /// Blends from `full` at full health to `damaged` as a brick wears down.
+    ByHealth { full: [f32; 4], damaged: [f32; 4] },
+}
+
+/// Colours for everything drawn in the playfield.
+pub struct Theme {
+    pub name: &'static str,
+    pub clear: [f32; 4],           // Canvas outside the camera's viewport
+    pub background: [[f32; 4]; 2], // Bottom and top of the sky gradient
+    pub ground: [f32; 4],
+    pub base: [f32; 4],    // Cannon base
+    pub support: [f32; 4], // Posts either side of the base
+    pub barrel: [f32; 4],
+    pub players: [[f32; 4]; 2], // Muzzle band and bullets of each player
+    pub bricks: BrickColors,
+    pub brick_hit: [f32; 4], // Flash when a brick is hit
+    pub shield: [f32; 4],
+    pub muzzle_flash: [[f32; 4]; 2], // Sparks at birth and at death
+    pub smoke: [[f32; 4]; 2],
+    pub text: [f32; 4],
+    pub text_shadow: [f32; 4],
+}
+
+pub const DARK: Theme = Theme {
+    name: "dark",
+    clear: [0.1, 0.2, 0.3, 1.0],
+    background: [[0.2, 0.3, 0.5, 1.0], [0.5, 0.7, 1.0, 1.0]],
+    ground: [0.3, 0.2, 0.1, 1.0],
+    base: [0.4, 0.4, 0.4, 1.0],
+    support: [0.3, 0.3, 0.3, 1.0],
+    barrel: [0.6, 0.6, 0.6, 1.0],
+    players: [[0.0, 1.0, 0.0, 1.0], [1.0, 0.4, 0.8, 1.0]],
+    bricks: BrickColors::ByPosition {
+        saturation: 0.7,
+        value: 1.0,
+    },
+    brick_hit: [1.0, 1.0, 1.0, 1.0],
+    shield: [0.3, 0.5, 1.0, 0.5],
+    muzzle_flash: [[1.0, 0.7, 0.2, 1.0], [1.0, 0.2, 0.0, 0.0]],
+    smoke: [[0.5, 0.5, 0.5, 0.6], [0.3, 0.3, 0.3, 0.0]],
+    text: [1.0, 1.0, 1.0, 1.0],
+    text_shadow: [0.0, 0.0, 0.0, 0.5],
+};
+
+pub const LIGHT: Theme = Theme {
+    name: "light",
+    clear: [0.85, 0.88, 0.92, 1.0],
+    background: [[0.8, 0.88, 0.95, 1.0], [0.98, 0.99, 1.0, 1.0]],
+    ground: [0.75, 0.65, 0.5, 1.0],
+    base: [0.55, 0.55, 0.6, 1.0],
+    support: [0.45, 0.45, 0.5, 1.0],
+    barrel: [0.35, 0.35, 0.4, 1.0],
+    players: [[0.0, 0.55, 0.2, 1.0], [0.8, 0.1, 0.5, 1.0]],
+    bricks: BrickColors::ByPosition {
+        saturation: 0.55,
+        value: 0.85,
+    },
+    brick_hit: [0.2, 0.2, 0.2, 1.0],
+    shield: [0.1, 0.3, 0.9, 0.5],
+    muzzle_flash: [[1.0, 0.5, 0.0, 1.0], [0.8, 0.1, 0.0, 0.0]],
+    smoke: [[0.3, 0.3, 0.3, 0.5], [0.5, 0.5, 0.5, 0.0]],
+    text: [0.1, 0.1, 0.15, 1.0],
+    text_shadow: [1.0, 1.0, 1.0, 0.6],
+};
+
+/// Pure black and white with saturated accents, for low vision.
+pub const HIGH_CONTRAST: Theme = Theme {
+    name: "high-contrast",
+    clear: [0.0, 0.0, 0.0, 1.0],
+    background: [[0.0, 0.0, 0.0, 1.0], [0.0, 0.0, 0.0, 1.0]],
+    ground: [0.5, 0.5, 0.5, 1.0],
+    base: [1.0, 1.0, 1.0, 1.0],
+    support: [1.0, 1.0, 1.0, 1.0],
+    barrel: [1.0, 1.0, 1.0, 1.0],
+    players: [[1.0, 1.0, 0.0, 1.0], [0.0, 1.0, 1.0, 1.0]],
+    bricks: BrickColors::ByHealth {
+        full: [1.0, 1.0, 1.0, 1.0],
+        damaged: [1.0, 0.0, 1.0, 1.0],
+    },
+    brick_hit: [1.0, 1.0, 0.0, 1.0],
+    shield: [0.0, 1.0, 1.0, 0.8],
+    muzzle_flash: [[1.0, 1.0, 0.0, 1.0], [1.0, 1.0, 1.0, 0.0]],
+    smoke: [[0.7, 0.7, 0.7, 0.6], [0.7, 0.7, 0.7, 0.0]],
+    text: [1.0, 1.0, 1.0, 1.0],
+    text_shadow: [0.0, 0.0, 0.0, 1.0],
+};
+
+/// Blue and orange from the Okabe-Ito palette, which stay distinct with deuteranopia and
+/// protanopia. Brick health runs along the blue-yellow axis these viewers still see.
+pub const COLOR_BLIND: Theme = Theme {
+    name: "color-blind",
+    clear: [0.1, 0.1, 0.15, 1.0],
+    background: [[0.1, 0.15, 0.25, 1.0], [0.3, 0.4, 0.55, 1.0]],
+    ground: [0.25, 0.25, 0.25, 1.0],
+    base: [0.5, 0.5, 0.5, 1.0],
+    support: [0.35, 0.35, 0.35, 1.0],
+    barrel: [0.7, 0.7, 0.7, 1.0],
+    players: [[0.34, 0.71, 0.91, 1.0], [0.9, 0.62, 0.0, 1.0]],
+    bricks: BrickColors::ByHealth {
+        full: [0.0, 0.45, 0.7, 1.0],
+        damaged: [0.94, 0.89, 0.26, 1.0],
+    },
+    brick_hit: [1.0, 1.0, 1.0, 1.0],
+    shield: [0.34, 0.71, 0.91, 0.5],
+    muzzle_flash: [[0.94, 0.89, 0.26, 1.0], [0.9, 0.62, 0.0, 0.0]],
+    smoke: [[0.5, 0.5, 0.5, 0.6], [0.3, 0.3, 0.3, 0.0]],
+    text: [1.0, 1.0, 1.0, 1.0],
+    text_shadow: [0.0, 0.0, 0.0, 0.5],
+};
+
+pub const THEMES: [&Theme; 4] = [&DARK, &LIGHT, &HIGH_CONTRAST, &COLOR_BLIND];
+
+impl Theme {
+    /// Built-in theme called `name`. The colour-blind palette also answers to the names of the
+    /// conditions it is designed for.
+    pub fn by_name(name: &str) -> Option<&'static Theme> {
+        match name {
+            "deuteranopia" | "protanopia" => Some(&COLOR_BLIND),
+            _ => THEMES.iter().copied().find(|theme| theme.name == name),
+        }
+    }
+
+    /// Colour of a brick whose left edge is at `x` with `health_fraction` of its health left.
+    pub fn brick_color(&self, x: f32, health_fraction: f32) -> [f32; 4] {
+        match self.bricks {
+            BrickColors::ByPosition { saturation, value } => {
+                let hue = ((x + 1.0) / 2.0) * 360.0;
+                let [r, g, b] = hsv_to_rgb(hue, saturation, value);
+                [r, g, b, 1.0]
+            }
+            BrickColors::ByHealth { full, damaged } => {
+                let t = 1.0 - health_fraction.clamp(0.0, 1.0);
+                let mut color = [0.0; 4];
+                for channel in 0..4 {
+                    color[channel] = full[channel] + (damaged[channel] - full[channel]) * t;
+                }
+                color
+            }
+        }
+    }
+}
+
+/// Convert HSV to RGB for brick colors.
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let c = v * s;
+    let h_prime = (h % 360.0) / 60.0;
+    let x = c * (1.0 - (h_prime % 2.0 - 1.0).abs());
+    let (r1, g1, b1) = if h_prime < 1.0 {
+        (c, x, 0.0)
+    } else if h_prime < 2.0 {
+        (x, c, 0.0)
+    } else if h_prime < 3.0 {
+        (0.0, c, x)
+    } else if h_prime < 4.0 {
+        (0.0, x, c)
+    } else if h_prime < 5.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+    let m = v - c;
+    [r1 + m, g1 + m, b1 + m]
+}
+
+/// The theme chosen in an earlier session, shared by every game.
+pub fn saved_theme(storage: &dyn Storage) -> &'static Theme {
+    storage
+        .load(THEME_KEY)
+        .and_then(|name| Theme::by_name(&name))
+        .unwrap_or(&DARK)
+}
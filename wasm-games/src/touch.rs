@@ -0,0 +1,431 @@
+use crate::player::{Player, BASE_HEIGHT, CANNON_Y};
+use crate::renderer::Renderer;
+
+// ### Touch Controls
+
+const DRIVE_ZONE_TOP: f32 = CANNON_Y - BASE_HEIGHT / 2.0; // Touches below this drive the cannon
+const DRIVE_TOUCH_RANGE: f32 = 0.1; // Distance at which a drive touch gives full throttle
+const FIRE_BUTTON_RADIUS: f32 = 0.1; // On-screen fire button
+const JOYSTICK_RADIUS: f32 = 0.15; // Deflection of the virtual joystick at full throttle
+const JOYSTICK_DEAD_ZONE: f32 = 0.2; // Fraction of the joystick radius that doesn't drive
+const JOYSTICK_ZONE_TOP: f32 = -0.4; // Touches below this on the left of a half grab the joystick
+const DRAG_DEAD_ZONE: f32 = 0.05; // Distance a touch moves before it starts aiming
+
+/// How touches control the cannons. Every scheme follows each finger on its own, so one thumb
+/// can aim or drive while the other fires.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TouchScheme {
+    /// Drag above the ground to aim and hold the fire button to fire. Touching the ground drives
+    /// towards the touch.
+    DragToAim,
+    /// Drag to aim with a fire button, and drive with a joystick that appears wherever the lower
+    /// left of a player's half is touched.
+    Joystick,
+    /// The barrel aims at a touch and fires while it is held. Touching the ground drives.
+    TapToFire,
+}
+
+impl TouchScheme {
+    pub const ALL: [TouchScheme; 3] = [
+        TouchScheme::DragToAim,
+        TouchScheme::Joystick,
+        TouchScheme::TapToFire,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TouchScheme::DragToAim => "drag-to-aim",
+            TouchScheme::Joystick => "joystick",
+            TouchScheme::TapToFire => "tap-to-fire",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<TouchScheme> {
+        TouchScheme::ALL
+            .iter()
+            .copied()
+            .find(|scheme| scheme.name() == name)
+    }
+
+    fn has_fire_button(self) -> bool {
+        self != TouchScheme::TapToFire
+    }
+}
+
+/// What a finger on the screen is doing.
+enum Role {
+    Aim { start: (f32, f32), dragging: bool }, // Aims once it leaves the drag dead zone
+    TapFire,
+    FireButton,
+    Drive,
+    Joystick { origin: (f32, f32) },
+}
+
+struct ActiveTouch {
+    id: u64,
+    player: usize,
+    position: (f32, f32), // Latest position in game coordinates
+    role: Role,
+}
+
+/// Turns touches into aiming, firing and driving for the player whose screen half they land in.
+pub struct TouchControls {
+    scheme: TouchScheme,
+    pub joystick_dead_zone: f32, // Fraction of the joystick radius that doesn't drive
+    pub drag_dead_zone: f32,     // Distance a drag moves before it aims, in world units
+    touches: Vec<ActiveTouch>,
+    used: bool, // Controls are only drawn once the screen has been touched
+}
+
+impl TouchControls {
+    pub fn new(scheme: TouchScheme) -> Self {
+        TouchControls {
+            scheme,
+            joystick_dead_zone: JOYSTICK_DEAD_ZONE,
+            drag_dead_zone: DRAG_DEAD_ZONE,
+            touches: Vec::new(),
+            used: false,
+        }
+    }
+
+    /// The same controls for a new game, with every touch let go.
+    pub fn restarted(&self) -> Self {
+        TouchControls {
+            touches: Vec::new(),
+            ..*self
+        }
+    }
+
+    /// Switches scheme, letting go of every touch so nothing stays held.
+    pub fn set_scheme(&mut self, scheme: TouchScheme, players: &mut [Player]) {
+        let ids: Vec<u64> = self.touches.iter().map(|touch| touch.id).collect();
+        for id in ids {
+            self.ended(id, players);
+        }
+        self.scheme = scheme;
+    }
+
+    pub fn started(&mut self, id: u64, x: f32, y: f32, players: &mut [Player]) {
+        self.used = true;
+        let index = if players.len() > 1 && x >= 0.0 { 1 } else { 0 };
+        let (left, right) = screen_half(index, players.len());
+        let (button_x, button_y) = fire_button_center(index, players.len());
+        let on_button = distance((x, y), (button_x, button_y)) <= FIRE_BUTTON_RADIUS;
+        let player = &mut players[index];
+
+        let role = match self.scheme {
+            scheme if scheme.has_fire_button() && on_button => {
+                player.fire_pressed = true;
+                Role::FireButton
+            }
+            TouchScheme::Joystick if x < (left + right) / 2.0 && y < JOYSTICK_ZONE_TOP => {
+                Role::Joystick { origin: (x, y) }
+            }
+            TouchScheme::DragToAim | TouchScheme::TapToFire if y < DRIVE_ZONE_TOP => Role::Drive,
+            TouchScheme::TapToFire => {
+                player.aim_at(x, y);
+                player.fire_pressed = true;
+                Role::TapFire
+            }
+            _ => Role::Aim {
+                start: (x, y),
+                dragging: false,
+            },
+        };
+        self.touches.push(ActiveTouch {
+            id,
+            player: index,
+            position: (x, y),
+            role,
+        });
+    }
+
+    pub fn moved(&mut self, id: u64, x: f32, y: f32, players: &mut [Player]) {
+        let drag_dead_zone = self.drag_dead_zone;
+        let touch = match self.touches.iter_mut().find(|touch| touch.id == id) {
+            Some(touch) => touch,
+            None => return,
+        };
+        touch.position = (x, y);
+        let player = &mut players[touch.player];
+        match &mut touch.role {
+            Role::Aim { start, dragging } => {
+                if !*dragging && distance(*start, (x, y)) >= drag_dead_zone {
+                    *dragging = true;
+                }
+                if *dragging {
+                    player.aim_at(x, y);
+                }
+            }
+            Role::TapFire => player.aim_at(x, y),
+            Role::FireButton | Role::Drive | Role::Joystick { .. } => {}
+        }
+    }
+
+    pub fn ended(&mut self, id: u64, players: &mut [Player]) {
+        let position = match self.touches.iter().position(|touch| touch.id == id) {
+            Some(position) => position,
+            None => return,
+        };
+        let touch = self.touches.remove(position);
+        if let Role::FireButton | Role::TapFire = touch.role {
+            // Another finger may still be holding fire
+            let still_firing = self.touches.iter().any(|other| {
+                other.player == touch.player
+                    && matches!(other.role, Role::FireButton | Role::TapFire)
+            });
+            if !still_firing {
+                players[touch.player].fire_pressed = false;
+            }
+        }
+    }
+
+    /// Sets each player's touch throttle and whether a touch is aiming, once per frame.
+    pub fn update(&self, players: &mut [Player]) {
+        for (index, player) in players.iter_mut().enumerate() {
+            let mut throttle = 0.0;
+            let mut aiming = false;
+            for touch in self.touches.iter().filter(|touch| touch.player == index) {
+                match touch.role {
+                    Role::Drive => {
+                        throttle += (touch.position.0 - player.cannon_x) / DRIVE_TOUCH_RANGE;
+                    }
+                    Role::Joystick { origin } => {
+                        throttle += self.joystick_throttle(origin, touch.position);
+                    }
+                    Role::Aim { dragging, .. } => aiming |= dragging,
+                    Role::TapFire => aiming = true,
+                    Role::FireButton => {}
+                }
+            }
+            player.touch_throttle = throttle.clamp(-1.0, 1.0);
+            player.touch_aiming = aiming;
+        }
+    }
+
+    /// Horizontal joystick deflection past the dead zone, rescaled to -1.0..1.0.
+    fn joystick_throttle(&self, origin: (f32, f32), position: (f32, f32)) -> f32 {
+        let deflection = ((position.0 - origin.0) / JOYSTICK_RADIUS).clamp(-1.0, 1.0);
+        if deflection.abs() < self.joystick_dead_zone {
+            return 0.0;
+        }
+        (deflection.abs() - self.joystick_dead_zone) / (1.0 - self.joystick_dead_zone)
+            * deflection.signum()
+    }
+
+    /// Draws the fire buttons, joysticks and drag markers in `color`.
+    pub fn draw(&self, renderer: &mut dyn Renderer, players: usize, color: [f32; 4]) {
+        if !self.used {
+            return;
+        }
+        let faded = |alpha: f32| [color[0], color[1], color[2], color[3] * alpha];
+
+        if self.scheme.has_fire_button() {
+            for index in 0..players {
+                let (x, y) = fire_button_center(index, players);
+                let held = self
+                    .touches
+                    .iter()
+                    .any(|touch| touch.player == index && matches!(touch.role, Role::FireButton));
+                let alpha = if held { 0.5 } else { 0.25 };
+                renderer.circle(x, y, FIRE_BUTTON_RADIUS, faded(alpha), 32);
+                renderer.circle(x, y, FIRE_BUTTON_RADIUS * 0.4, faded(alpha), 16);
+            }
+        }
+
+        for touch in &self.touches {
+            let (x, y) = touch.position;
+            match touch.role {
+                Role::Joystick { origin } => {
+                    // Base, dead zone and the knob, which stops at the rim
+                    renderer.circle(origin.0, origin.1, JOYSTICK_RADIUS, faded(0.2), 32);
+                    renderer.circle(
+                        origin.0,
+                        origin.1,
+                        JOYSTICK_RADIUS * self.joystick_dead_zone,
+                        faded(0.2),
+                        16,
+                    );
+                    let (dx, dy) = (x - origin.0, y - origin.1);
+                    let scale = (JOYSTICK_RADIUS / distance(origin, (x, y)).max(1e-6)).min(1.0);
+                    renderer.circle(
+                        origin.0 + dx * scale,
+                        origin.1 + dy * scale,
+                        JOYSTICK_RADIUS * 0.4,
+                        faded(0.5),
+                        24,
+                    );
+                }
+                Role::Aim {
+                    start,
+                    dragging: false,
+                } => {
+                    renderer.circle(start.0, start.1, self.drag_dead_zone, faded(0.2), 24);
+                }
+                Role::Aim { .. } | Role::TapFire | Role::Drive => {
+                    renderer.circle(x, y, FIRE_BUTTON_RADIUS * 0.3, faded(0.3), 16);
+                }
+                Role::FireButton => {}
+            }
+        }
+    }
+}
+
+/// Left and right edges of the part of the screen player `index` of `players` touches in.
+fn screen_half(index: usize, players: usize) -> (f32, f32) {
+    match (players, index) {
+        (1, _) => (-1.0, 1.0),
+        (_, 0) => (-1.0, 0.0),
+        _ => (0.0, 1.0),
+    }
+}
+
+/// The fire button sits in the bottom right corner of each player's half, above the ground.
+fn fire_button_center(index: usize, players: usize) -> (f32, f32) {
+    let (_, right) = screen_half(index, players);
+    (
+        right - FIRE_BUTTON_RADIUS * 1.5,
+        DRIVE_ZONE_TOP + FIRE_BUTTON_RADIUS * 2.5,
+    )
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GameConfig;
+
+    fn players(count: usize) -> Vec<Player> {
+        let config = GameConfig::default();
+        match count {
+            1 => vec![Player::new(0.0, -1.0, 1.0, [1.0; 4], &config)],
+            _ => vec![
+                Player::new(-0.5, -1.0, 0.0, [1.0; 4], &config),
+                Player::new(0.5, 0.0, 1.0, [1.0; 4], &config),
+            ],
+        }
+    }
+
+    #[test]
+    fn drags_aim_once_past_the_dead_zone() {
+        let mut players = players(1);
+        let mut touch = TouchControls::new(TouchScheme::DragToAim);
+        touch.started(1, 0.0, 0.5, &mut players);
+        touch.moved(1, -0.02, 0.5, &mut players);
+        touch.update(&mut players);
+        assert_eq!(players[0].theta, 0.0);
+        assert!(!players[0].touch_aiming);
+
+        touch.moved(1, -0.5, 0.5, &mut players);
+        touch.update(&mut players);
+        assert!(players[0].theta > 0.0); // Turned left towards the finger
+        assert!(players[0].touch_aiming);
+        // Coming back inside the dead zone keeps aiming
+        touch.moved(1, 0.0, 0.5, &mut players);
+        assert_eq!(players[0].theta, 0.0);
+
+        touch.ended(1, &mut players);
+        touch.update(&mut players);
+        assert!(!players[0].touch_aiming);
+    }
+
+    #[test]
+    fn fire_button_fires_until_the_last_finger_lifts() {
+        let mut players = players(1);
+        let mut touch = TouchControls::new(TouchScheme::DragToAim);
+        let (x, y) = fire_button_center(0, 1);
+        touch.started(1, x, y, &mut players);
+        touch.started(2, x + 0.01, y, &mut players);
+        assert!(players[0].fire_pressed);
+        touch.ended(1, &mut players);
+        assert!(players[0].fire_pressed);
+        touch.ended(2, &mut players);
+        assert!(!players[0].fire_pressed);
+    }
+
+    #[test]
+    fn tap_to_fire_aims_at_the_finger_and_fires_while_held() {
+        let mut players = players(1);
+        let mut touch = TouchControls::new(TouchScheme::TapToFire);
+        touch.started(1, 0.5, 0.0, &mut players);
+        assert!(players[0].fire_pressed);
+        assert!(players[0].theta < 0.0); // Turned right
+        let first_aim = players[0].theta;
+        touch.moved(1, 0.8, 0.0, &mut players);
+        assert!(players[0].theta < first_aim);
+        touch.ended(1, &mut players);
+        assert!(!players[0].fire_pressed);
+    }
+
+    #[test]
+    fn touching_the_ground_drives_towards_the_finger() {
+        let mut players = players(1);
+        let mut touch = TouchControls::new(TouchScheme::DragToAim);
+        touch.started(1, 0.05, DRIVE_ZONE_TOP - 0.05, &mut players);
+        touch.update(&mut players);
+        assert!((players[0].touch_throttle - 0.5).abs() < 1e-6);
+        touch.moved(1, -0.9, DRIVE_ZONE_TOP - 0.05, &mut players);
+        touch.update(&mut players);
+        assert_eq!(players[0].touch_throttle, -1.0);
+        touch.ended(1, &mut players);
+        touch.update(&mut players);
+        assert_eq!(players[0].touch_throttle, 0.0);
+    }
+
+    #[test]
+    fn joystick_ignores_the_dead_zone_and_rescales_the_rest() {
+        let mut players = players(1);
+        let mut touch = TouchControls::new(TouchScheme::Joystick);
+        let (x, y) = (-0.5, -0.6);
+        touch.started(1, x, y, &mut players);
+        let throttle_at = |touch: &mut TouchControls, players: &mut Vec<Player>, dx: f32| {
+            touch.moved(1, x + dx, y, players);
+            touch.update(players);
+            players[0].touch_throttle
+        };
+        // Within the dead zone, a fifth of the radius
+        assert_eq!(throttle_at(&mut touch, &mut players, 0.02), 0.0);
+        // Three fifths of the way out is halfway past the dead zone
+        let throttle = throttle_at(&mut touch, &mut players, -0.09);
+        assert!((throttle + 0.5).abs() < 1e-6);
+        assert_eq!(throttle_at(&mut touch, &mut players, 0.5), 1.0);
+
+        touch.joystick_dead_zone = 0.0;
+        let throttle = throttle_at(&mut touch, &mut players, 0.03);
+        assert!((throttle - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn co_op_touches_go_to_the_player_whose_half_they_land_in() {
+        let mut players = players(2);
+        let mut touch = TouchControls::new(TouchScheme::DragToAim);
+        let (x, y) = fire_button_center(1, 2);
+        touch.started(1, x, y, &mut players);
+        assert!(!players[0].fire_pressed);
+        assert!(players[1].fire_pressed);
+        // Dragging anywhere else in the right half aims for player two
+        touch.started(2, 0.5, 0.2, &mut players);
+        touch.moved(2, 0.5, 0.5, &mut players);
+        touch.update(&mut players);
+        assert!(!players[0].touch_aiming);
+        assert!(players[1].touch_aiming);
+    }
+
+    #[test]
+    fn switching_scheme_lets_go_of_every_touch() {
+        let mut players = players(1);
+        let mut touch = TouchControls::new(TouchScheme::TapToFire);
+        touch.started(1, 0.5, 0.0, &mut players);
+        touch.set_scheme(TouchScheme::Joystick, &mut players);
+        assert!(!players[0].fire_pressed);
+        // A late move or release of the old touch is ignored
+        touch.moved(1, 0.0, 0.0, &mut players);
+        touch.ended(1, &mut players);
+        touch.update(&mut players);
+        assert!(!players[0].touch_aiming);
+        assert!(touch.touches.is_empty());
+    }
+}
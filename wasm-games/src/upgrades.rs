@@ -0,0 +1,309 @@
+use crate::storage::Storage;
+
+// ### Upgrade Tree
+
+/// Weapon stat improved by an upgrade.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpgradeStat {
+    RotationSpeed,
+    FireRate,
+    SpreadReduction,
+    BulletSize,
+    Damage,
+}
+
+/// A purchasable upgrade. Each level costs the next entry in `costs` and adds `per_level` to
+/// the stat's bonus.
+pub struct UpgradeDef {
+    pub stat: UpgradeStat,
+    pub id: &'static str, // Key used when saving progress
+    pub name: &'static str,
+    pub costs: &'static [u32],
+    pub per_level: f32,
+    pub requires: Option<(UpgradeStat, u32)>, // Parent upgrade and level needed to unlock
+}
+
+impl UpgradeDef {
+    pub fn max_level(&self) -> u32 {
+        self.costs.len() as u32
+    }
+}
+
+pub const UPGRADES: [UpgradeDef; 5] = [
+    UpgradeDef {
+        stat: UpgradeStat::RotationSpeed,
+        id: "rotation_speed",
+        name: "Rotation speed",
+        costs: &[10, 20, 40],
+        per_level: 0.25, // +25% turn rate
+        requires: None,
+    },
+    UpgradeDef {
+        stat: UpgradeStat::FireRate,
+        id: "fire_rate",
+        name: "Fire rate",
+        costs: &[15, 30, 60, 120],
+        per_level: 0.2, // +20% shots per second
+        requires: None,
+    },
+    UpgradeDef {
+        stat: UpgradeStat::SpreadReduction,
+        id: "spread_reduction",
+        name: "Accuracy",
+        costs: &[20, 40, 80],
+        per_level: 0.25, // -25% spread
+        requires: Some((UpgradeStat::FireRate, 1)),
+    },
+    UpgradeDef {
+        stat: UpgradeStat::BulletSize,
+        id: "bullet_size",
+        name: "Bullet size",
+        costs: &[20, 50, 100],
+        per_level: 0.3, // +30% radius
+        requires: None,
+    },
+    UpgradeDef {
+        stat: UpgradeStat::Damage,
+        id: "damage",
+        name: "Damage",
+        costs: &[60, 150],
+        per_level: 1.0, // +1 damage per hit
+        requires: Some((UpgradeStat::BulletSize, 2)),
+    },
+];
+
+fn index_of(stat: UpgradeStat) -> usize {
+    UPGRADES
+        .iter()
+        .position(|def| def.stat == stat)
+        .expect("Every stat has an upgrade definition")
+}
+
+// ### Persistent Progress
+
+const PROGRESS_KEY: &str = "wasm-games.bricks.progress";
+
+/// Currency and purchased upgrade levels, persisted across sessions.
+///
+/// Every game on the page shares the saved progress, so saving merges: the changes made since
+/// this copy was loaded or last saved are applied to whatever is stored now.
+pub struct Progress {
+    pub currency: u32,
+    levels: [u32; UPGRADES.len()],
+    saved_currency: u32, // Values as last loaded or saved
+    saved_levels: [u32; UPGRADES.len()],
+}
+
+impl Progress {
+    /// Loads saved progress, starting fresh if nothing was saved or it can't be parsed.
+    ///
+    /// Progress is saved as `key=value` lines: `currency` plus one line per upgrade `id`.
+    pub fn load(storage: &dyn Storage) -> Self {
+        let mut progress = Progress {
+            currency: 0,
+            levels: [0; UPGRADES.len()],
+            saved_currency: 0,
+            saved_levels: [0; UPGRADES.len()],
+        };
+        let saved = match storage.load(PROGRESS_KEY) {
+            Some(saved) => saved,
+            None => return progress,
+        };
+        for line in saved.lines() {
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => continue,
+            };
+            let value = match value.parse::<u32>() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            if key == "currency" {
+                progress.currency = value;
+            } else if let Some(index) = UPGRADES.iter().position(|def| def.id == key) {
+                progress.levels[index] = value.min(UPGRADES[index].max_level());
+            }
+        }
+        progress.saved_currency = progress.currency;
+        progress.saved_levels = progress.levels;
+        progress
+    }
+
+    /// Saves this copy's changes on top of the stored progress, then takes on the merged
+    /// values, including whatever other games saved meanwhile.
+    pub fn save(&mut self, storage: &mut dyn Storage) {
+        let stored = Progress::load(storage);
+        self.currency = merge(stored.currency, self.saved_currency, self.currency);
+        for (index, def) in UPGRADES.iter().enumerate() {
+            let level = merge(
+                stored.levels[index],
+                self.saved_levels[index],
+                self.levels[index],
+            );
+            self.levels[index] = level.min(def.max_level());
+        }
+        self.saved_currency = self.currency;
+        self.saved_levels = self.levels;
+
+        let mut saved = format!("currency={}\n", self.currency);
+        for (def, level) in UPGRADES.iter().zip(self.levels.iter()) {
+            saved.push_str(&format!("{}={}\n", def.id, level));
+        }
+        storage.save(PROGRESS_KEY, &saved);
+    }
+
+    pub fn level(&self, stat: UpgradeStat) -> u32 {
+        self.levels[index_of(stat)]
+    }
+
+    /// Total bonus from all purchased levels of `stat`.
+    pub fn bonus(&self, stat: UpgradeStat) -> f32 {
+        let index = index_of(stat);
+        self.levels[index] as f32 * UPGRADES[index].per_level
+    }
+
+    /// Whether the parent upgrade of `UPGRADES[index]` is far enough along.
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        match UPGRADES[index].requires {
+            Some((stat, level)) => self.level(stat) >= level,
+            None => true,
+        }
+    }
+
+    /// Cost of the next level of `UPGRADES[index]`, or `None` if it is maxed out.
+    pub fn next_cost(&self, index: usize) -> Option<u32> {
+        UPGRADES[index]
+            .costs
+            .get(self.levels[index] as usize)
+            .copied()
+    }
+
+    /// Buys the next level of `UPGRADES[index]`, returning whether the purchase went through.
+    pub fn purchase(&mut self, index: usize) -> bool {
+        if index >= UPGRADES.len() || !self.is_unlocked(index) {
+            return false;
+        }
+        match self.next_cost(index) {
+            Some(cost) if cost <= self.currency => {
+                self.currency -= cost;
+                self.levels[index] += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// `stored` changed by as much as `current` differs from `base`, kept within a u32.
+fn merge(stored: u32, base: u32, current: u32) -> u32 {
+    let merged = stored as i64 + current as i64 - base as i64;
+    merged.clamp(0, u32::MAX as i64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    /// Progress with `currency` coins and nothing bought, as if loaded from empty storage.
+    fn with_coins(currency: u32) -> Progress {
+        let mut progress = Progress::load(&MemoryStorage::default());
+        progress.currency = currency;
+        progress
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut storage = MemoryStorage::default();
+        let mut progress = with_coins(100);
+        assert!(progress.purchase(1)); // Fire rate, 15
+        assert!(progress.purchase(1)); // 30
+        assert!(progress.purchase(3)); // Bullet size, 20
+        progress.save(&mut storage);
+
+        let loaded = Progress::load(&storage);
+        assert_eq!(loaded.currency, 35);
+        assert_eq!(loaded.levels, [0, 2, 0, 1, 0]);
+    }
+
+    #[test]
+    fn load_skips_bad_lines_and_clamps_levels() {
+        let mut storage = MemoryStorage::default();
+        storage.save(
+            PROGRESS_KEY,
+            "currency=12\nfire_rate=99\nbullet_size=x\nnonsense\nunknown=3\n",
+        );
+        let loaded = Progress::load(&storage);
+        assert_eq!(loaded.currency, 12);
+        assert_eq!(loaded.level(UpgradeStat::FireRate), 4);
+        assert_eq!(loaded.level(UpgradeStat::BulletSize), 0);
+    }
+
+    #[test]
+    fn purchase_spends_coins_and_raises_the_bonus() {
+        let mut progress = with_coins(30);
+        assert_eq!(progress.next_cost(0), Some(10));
+        assert!(progress.purchase(0));
+        assert_eq!(progress.currency, 20);
+        assert_eq!(progress.level(UpgradeStat::RotationSpeed), 1);
+        assert_eq!(progress.bonus(UpgradeStat::RotationSpeed), 0.25);
+        assert_eq!(progress.next_cost(0), Some(20));
+    }
+
+    #[test]
+    fn purchase_needs_enough_coins() {
+        let mut progress = with_coins(9);
+        assert!(!progress.purchase(0));
+        assert_eq!(progress.currency, 9);
+        assert_eq!(progress.level(UpgradeStat::RotationSpeed), 0);
+    }
+
+    #[test]
+    fn purchase_stops_at_the_max_level() {
+        let mut progress = with_coins(1000);
+        for _ in 0..3 {
+            assert!(progress.purchase(0));
+        }
+        assert_eq!(progress.next_cost(0), None);
+        assert!(!progress.purchase(0));
+        assert_eq!(progress.currency, 930);
+    }
+
+    #[test]
+    fn purchase_needs_the_parent_upgrade() {
+        let mut progress = with_coins(1000);
+        // Accuracy needs a level of fire rate
+        assert!(!progress.is_unlocked(2));
+        assert!(!progress.purchase(2));
+        assert!(progress.purchase(1));
+        assert!(progress.purchase(2));
+        // Out-of-range upgrades are refused
+        assert!(!progress.purchase(UPGRADES.len()));
+    }
+
+    #[test]
+    fn saving_merges_with_other_games() {
+        let mut storage = MemoryStorage::default();
+        storage.save(PROGRESS_KEY, "currency=100\n");
+        let mut first = Progress::load(&storage);
+        let mut second = Progress::load(&storage);
+
+        first.currency += 30;
+        assert!(first.purchase(0)); // Costs 10
+        first.save(&mut storage);
+        second.currency += 5;
+        second.save(&mut storage);
+
+        // Each game keeps the other's coins and purchases
+        assert_eq!(second.currency, 125);
+        assert_eq!(second.level(UpgradeStat::RotationSpeed), 1);
+        let stored = Progress::load(&storage);
+        assert_eq!(stored.currency, 125);
+        assert_eq!(stored.level(UpgradeStat::RotationSpeed), 1);
+        // Saving again without changes keeps everything as stored
+        first.save(&mut storage);
+        assert_eq!(first.currency, 125);
+        assert_eq!(Progress::load(&storage).currency, 125);
+    }
+}
@@ -0,0 +1,443 @@
+use crate::player::barrel_tip;
+use crate::playfield::BALL_RADIUS;
+use crate::random::random;
+use crate::upgrades::{Progress, UpgradeStat};
+
+// ### Weapon Trait and Implementation
+
+const HEAVY_DAMAGE: u32 = 4; // Base damage of the heavy cannon's shells
+
+/// Represents a bullet fired from the cannon.
+pub struct Bullet {
+    pub x: f32,
+    pub y: f32,
+    pub dx: f32,
+    pub dy: f32,
+    pub radius: f32,
+    pub health: u32,
+    pub damage: u32,
+    pub owner: usize,            // Index of the player who fired it
+    pub pierce: u32,             // Bricks this bullet can still pass through
+    pub last_hit: Option<usize>, // Brick hit most recently, so piercing bullets hit it once
+}
+
+impl Bullet {
+    /// Copy of this bullet with its velocity rotated by `angle` radians.
+    pub fn split(&self, angle: f32) -> Bullet {
+        let (sin, cos) = angle.sin_cos();
+        Bullet {
+            x: self.x,
+            y: self.y,
+            dx: self.dx * cos - self.dy * sin,
+            dy: self.dx * sin + self.dy * cos,
+            radius: self.radius,
+            health: self.health,
+            damage: self.damage,
+            owner: self.owner,
+            pierce: self.pierce,
+            last_hit: self.last_hit,
+        }
+    }
+}
+
+pub trait Weapon {
+    /// Name shown in the HUD.
+    fn name(&self) -> &'static str;
+    fn update(&mut self, delta_time: f32);
+    fn try_fire(&mut self, cannon_position: (f32, f32), cannon_angle: f32) -> Option<Bullet>;
+    /// Heat gauge, if the weapon overheats.
+    fn heat(&self) -> Option<&HeatGauge>;
+    /// Magazine, if the weapon uses finite ammunition.
+    fn magazine(&self) -> Option<&Magazine>;
+    /// Starts a reload if the weapon uses magazines.
+    fn reload(&mut self);
+    /// Adds rounds to the reserve if the weapon uses magazines.
+    fn add_ammo(&mut self, rounds: u32);
+    /// Scales the rate of fire, e.g. 2.0 halves the cooldown between shots.
+    fn set_fire_rate_multiplier(&mut self, multiplier: f32);
+}
+
+/// Heat that builds up with every shot and locks firing once it overheats.
+pub struct HeatGauge {
+    pub heat: f32, // 0.0 (cold) to 1.0 (overheated)
+    heat_per_shot: f32,
+    cool_rate: f32,
+    recover_level: f32,
+    pub overheated: bool,
+}
+
+impl HeatGauge {
+    pub fn new(heat_per_shot: f32, cool_rate: f32, recover_level: f32) -> Self {
+        HeatGauge {
+            heat: 0.0,
+            heat_per_shot,
+            cool_rate,
+            recover_level,
+            overheated: false,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.heat = (self.heat - self.cool_rate * delta_time).max(0.0);
+        if self.overheated && self.heat <= self.recover_level {
+            self.overheated = false;
+        }
+    }
+
+    pub fn can_fire(&self) -> bool {
+        !self.overheated
+    }
+
+    pub fn add_shot(&mut self) {
+        self.heat = (self.heat + self.heat_per_shot).min(1.0);
+        if self.heat >= 1.0 {
+            self.overheated = true;
+        }
+    }
+}
+
+/// Finite magazine refilled from a reserve of rounds after a reload delay.
+pub struct Magazine {
+    pub capacity: u32,
+    pub rounds: u32,
+    pub reserve: u32,
+    reload_time: f32,
+    reload_timer: f32, // Counts down while reloading
+}
+
+impl Magazine {
+    pub fn new(capacity: u32, reserve: u32, reload_time: f32) -> Self {
+        Magazine {
+            capacity,
+            rounds: capacity,
+            reserve,
+            reload_time,
+            reload_timer: 0.0,
+        }
+    }
+
+    pub fn is_reloading(&self) -> bool {
+        self.reload_timer > 0.0
+    }
+
+    /// Whether every round, loaded or in reserve, has been fired.
+    pub fn is_spent(&self) -> bool {
+        self.rounds == 0 && self.reserve == 0
+    }
+
+    /// Progress of the current reload from 0.0 to 1.0.
+    pub fn reload_progress(&self) -> f32 {
+        if self.is_reloading() {
+            1.0 - self.reload_timer / self.reload_time
+        } else {
+            1.0
+        }
+    }
+
+    pub fn start_reload(&mut self) {
+        if !self.is_reloading() && self.rounds < self.capacity && self.reserve > 0 {
+            self.reload_timer = self.reload_time;
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.is_reloading() {
+            self.reload_timer -= delta_time;
+            if self.reload_timer <= 0.0 {
+                self.reload_timer = 0.0;
+                let loaded = (self.capacity - self.rounds).min(self.reserve);
+                self.rounds += loaded;
+                self.reserve -= loaded;
+            }
+        } else if self.rounds == 0 {
+            // Reload automatically once the magazine runs dry
+            self.start_reload();
+        }
+    }
+
+    pub fn can_fire(&self) -> bool {
+        !self.is_reloading() && self.rounds > 0
+    }
+}
+
+/// Simple cannon implementation with cooldown and firing variation.
+pub struct SimpleCannon {
+    name: &'static str,
+    cooldown_timer: f32,
+    cooldown_min: f32,
+    cooldown_max: f32,
+    angle_spread: f32,
+    speed_min: f32,
+    speed_max: f32,
+    bullet_radius: f32,
+    fire_rate_multiplier: f32,
+    spread_multiplier: f32,
+    size_multiplier: f32,
+    base_damage: u32, // Damage before upgrades
+    damage: u32,
+    heat: Option<HeatGauge>,
+    magazine: Option<Magazine>,
+    random: fn() -> f64, // Uniform in 0..1, for spread, speed and cooldown
+}
+
+impl SimpleCannon {
+    pub fn new() -> Self {
+        SimpleCannon {
+            name: "Cannon",
+            cooldown_timer: 0.0,
+            cooldown_min: 0.005,
+            cooldown_max: 0.015,
+            angle_spread: 0.1,
+            speed_min: 0.8, // Fractions of the configured ball speed
+            speed_max: 1.2,
+            bullet_radius: BALL_RADIUS,
+            fire_rate_multiplier: 1.0,
+            spread_multiplier: 1.0,
+            size_multiplier: 1.0,
+            base_damage: 1,
+            damage: 1,
+            heat: None,
+            magazine: None,
+            random,
+        }
+    }
+
+    /// Slow, accurate shells that hit much harder.
+    pub fn heavy() -> Self {
+        SimpleCannon {
+            name: "Heavy cannon",
+            cooldown_min: 0.2,
+            cooldown_max: 0.25,
+            angle_spread: 0.02,
+            speed_min: 0.7,
+            speed_max: 0.8,
+            bullet_radius: BALL_RADIUS * 2.0,
+            base_damage: HEAVY_DAMAGE,
+            damage: HEAVY_DAMAGE,
+            ..SimpleCannon::new()
+        }
+    }
+
+    pub fn with_heat(mut self, heat: HeatGauge) -> Self {
+        self.heat = Some(heat);
+        self
+    }
+
+    pub fn with_magazine(mut self, magazine: Magazine) -> Self {
+        self.magazine = Some(magazine);
+        self
+    }
+
+    pub fn is_out_of_ammo(&self) -> bool {
+        self.magazine.as_ref().is_some_and(Magazine::is_spent)
+    }
+
+    /// Applies purchased upgrades. Fire rate is applied by the game each frame, since power-ups
+    /// also change it.
+    pub fn apply_upgrades(&mut self, progress: &Progress) {
+        self.spread_multiplier = (1.0 - progress.bonus(UpgradeStat::SpreadReduction)).max(0.0);
+        self.size_multiplier = 1.0 + progress.bonus(UpgradeStat::BulletSize);
+        self.damage = self.base_damage + progress.bonus(UpgradeStat::Damage) as u32;
+    }
+}
+
+impl Weapon for SimpleCannon {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn update(&mut self, delta_time: f32) {
+        self.cooldown_timer = (self.cooldown_timer - delta_time).max(0.0);
+        if let Some(heat) = &mut self.heat {
+            heat.update(delta_time);
+        }
+        if let Some(magazine) = &mut self.magazine {
+            magazine.update(delta_time);
+        }
+    }
+
+    fn try_fire(&mut self, cannon_position: (f32, f32), cannon_angle: f32) -> Option<Bullet> {
+        if self.cooldown_timer > 0.0 {
+            return None;
+        }
+        if self.heat.as_ref().is_some_and(|heat| !heat.can_fire()) {
+            return None;
+        }
+        if self
+            .magazine
+            .as_ref()
+            .is_some_and(|magazine| !magazine.can_fire())
+        {
+            return None;
+        }
+
+        let (cannon_x, cannon_y) = cannon_position;
+        let random = || (self.random)() as f32;
+        let random_angle_offset =
+            (random() * 2.0 - 1.0) * self.angle_spread * self.spread_multiplier;
+        let firing_angle = cannon_angle + random_angle_offset;
+        let random_speed = self.speed_min + random() * (self.speed_max - self.speed_min);
+        let dx = -random_speed * firing_angle.sin();
+        let dy = random_speed * firing_angle.cos();
+
+        let (bullet_x, bullet_y) = barrel_tip(cannon_x, cannon_y, firing_angle);
+
+        self.cooldown_timer = (self.cooldown_min
+            + random() * (self.cooldown_max - self.cooldown_min))
+            / self.fire_rate_multiplier;
+        if let Some(heat) = &mut self.heat {
+            heat.add_shot();
+        }
+        if let Some(magazine) = &mut self.magazine {
+            magazine.rounds -= 1;
+        }
+
+        Some(Bullet {
+            x: bullet_x,
+            y: bullet_y,
+            dx,
+            dy,
+            radius: self.bullet_radius * self.size_multiplier,
+            health: 1,
+            damage: self.damage,
+            owner: 0,
+            pierce: 0,
+            last_hit: None,
+        })
+    }
+
+    fn heat(&self) -> Option<&HeatGauge> {
+        self.heat.as_ref()
+    }
+
+    fn magazine(&self) -> Option<&Magazine> {
+        self.magazine.as_ref()
+    }
+
+    fn reload(&mut self) {
+        if let Some(magazine) = &mut self.magazine {
+            magazine.start_reload();
+        }
+    }
+
+    fn add_ammo(&mut self, rounds: u32) {
+        if let Some(magazine) = &mut self.magazine {
+            magazine.reserve += rounds;
+        }
+    }
+
+    fn set_fire_rate_multiplier(&mut self, multiplier: f32) {
+        self.fire_rate_multiplier = multiplier;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORIGIN: (f32, f32) = (0.0, 0.0);
+
+    /// A cannon whose spread, speed and cooldown always land mid-range.
+    fn steady(cannon: SimpleCannon) -> SimpleCannon {
+        SimpleCannon {
+            random: || 0.5,
+            ..cannon
+        }
+    }
+
+    #[test]
+    fn heat_locks_firing_until_cooled_to_the_recover_level() {
+        let mut heat = HeatGauge::new(0.25, 0.5, 0.5);
+        for _ in 0..3 {
+            heat.add_shot();
+        }
+        assert!(heat.can_fire());
+        heat.add_shot();
+        assert!(heat.overheated);
+        assert!(!heat.can_fire());
+
+        // Cooling part of the way isn't enough
+        heat.update(0.5);
+        assert_eq!(heat.heat, 0.75);
+        assert!(!heat.can_fire());
+        heat.update(0.5);
+        assert!(heat.can_fire());
+        // Heat bottoms out at cold
+        heat.update(10.0);
+        assert_eq!(heat.heat, 0.0);
+    }
+
+    #[test]
+    fn magazine_reloads_from_the_reserve_after_the_delay() {
+        let mut magazine = Magazine::new(4, 6, 1.0);
+        magazine.rounds = 1;
+        magazine.start_reload();
+        assert!(magazine.is_reloading());
+        assert!(!magazine.can_fire());
+        magazine.update(0.25);
+        assert_eq!(magazine.reload_progress(), 0.25);
+        assert_eq!(magazine.rounds, 1);
+        magazine.update(0.75);
+        assert!(!magazine.is_reloading());
+        assert_eq!((magazine.rounds, magazine.reserve), (4, 3));
+    }
+
+    #[test]
+    fn magazine_reloads_by_itself_once_dry_and_runs_out() {
+        let mut magazine = Magazine::new(4, 2, 1.0);
+        magazine.rounds = 0;
+        magazine.update(0.0);
+        assert!(magazine.is_reloading());
+        magazine.update(1.0);
+        // Only what the reserve held is loaded
+        assert_eq!((magazine.rounds, magazine.reserve), (2, 0));
+        magazine.rounds = 0;
+        magazine.update(0.0);
+        assert!(!magazine.is_reloading());
+        assert!(magazine.is_spent());
+    }
+
+    #[test]
+    fn full_magazines_and_empty_reserves_dont_reload() {
+        let mut full = Magazine::new(4, 6, 1.0);
+        full.start_reload();
+        assert!(!full.is_reloading());
+        let mut no_reserve = Magazine::new(4, 0, 1.0);
+        no_reserve.rounds = 2;
+        no_reserve.start_reload();
+        assert!(!no_reserve.is_reloading());
+    }
+
+    #[test]
+    fn cooldown_spaces_shots_and_scales_with_fire_rate() {
+        let mut cannon = steady(SimpleCannon::heavy()); // 0.225 second cooldown
+        assert!(cannon.try_fire(ORIGIN, 0.0).is_some());
+        assert!(cannon.try_fire(ORIGIN, 0.0).is_none());
+        cannon.update(0.2);
+        assert!(cannon.try_fire(ORIGIN, 0.0).is_none());
+        cannon.update(0.05);
+        assert!(cannon.try_fire(ORIGIN, 0.0).is_some());
+
+        cannon.set_fire_rate_multiplier(2.0);
+        cannon.update(1.0);
+        assert!(cannon.try_fire(ORIGIN, 0.0).is_some());
+        cannon.update(0.12);
+        assert!(cannon.try_fire(ORIGIN, 0.0).is_some());
+    }
+
+    #[test]
+    fn firing_uses_heat_and_rounds() {
+        let mut cannon = steady(SimpleCannon::new())
+            .with_heat(HeatGauge::new(0.6, 0.1, 0.0))
+            .with_magazine(Magazine::new(3, 0, 1.0));
+        let bullet = cannon.try_fire(ORIGIN, 0.0).unwrap();
+        // Mid-range spread fires straight along the barrel at mid-range speed
+        assert_eq!(bullet.dx, 0.0);
+        assert_eq!(bullet.dy, 1.0);
+        cannon.update(1.0);
+        cannon.try_fire(ORIGIN, 0.0).unwrap();
+        assert!(cannon.heat().unwrap().overheated);
+        assert_eq!(cannon.magazine().unwrap().rounds, 1);
+        assert!(cannon.try_fire(ORIGIN, 0.0).is_none());
+    }
+}